@@ -0,0 +1,115 @@
+//! Times hover testing with thousands of trackers of every shape, against
+//! testing every tracker each frame. Run with `cargo bench --bench hover`.
+
+// The game is built in as a module so the benchmark drives the same hover
+// systems, and most of it goes unused here
+#[allow(dead_code)]
+#[path = "../src/main.rs"]
+mod game;
+
+use bevy::prelude::*;
+use bevy::render::view::RenderLayers;
+use bevy::transform::TransformPlugin;
+use game::{
+    rebuild_hover_grid, update_hover_cache, update_mouse_hover_state, CameraPointer, HoverEnter, HoverExit,
+    HoverGrid, HoverShape, MouseHoverTracker, PointerPositions,
+};
+use std::time::Instant;
+
+fn main() {
+    const FRAMES: u32 = 500;
+
+    let pointer_at = |world_position| PointerPositions {
+        pointers: vec![CameraPointer {
+            camera: Entity::PLACEHOLDER,
+            order: 0,
+            layers: RenderLayers::default(),
+            world_position,
+        }],
+    };
+
+    for count in [1_000, 5_000, 20_000] {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, TransformPlugin, HierarchyPlugin))
+            .insert_resource(pointer_at(Vec2::ZERO))
+            .insert_resource(HoverGrid::default())
+            .add_event::<HoverEnter>()
+            .add_event::<HoverExit>()
+            .add_systems(Update, (update_hover_cache, rebuild_hover_grid, update_mouse_hover_state).chain());
+
+        // Lay the trackers out in a square, cycling through the shapes
+        let side = (count as f32).sqrt().ceil() as usize;
+        let spacing = 60.0;
+        let offset = side as f32 * spacing / 2.0;
+        for idx in 0..count {
+            let shape = match idx % 6 {
+                0 => HoverShape::Rectangle(Vec2::new(20.0, 15.0)),
+                1 => HoverShape::Circle(20.0),
+                2 => HoverShape::Polygon(vec![
+                    Vec2::new(-20.0, -20.0),
+                    Vec2::new(20.0, -20.0),
+                    Vec2::new(0.0, 0.0),
+                    Vec2::new(20.0, 20.0),
+                    Vec2::new(-20.0, 20.0),
+                ]),
+                3 => HoverShape::RoundedRectangle { half_size: Vec2::new(25.0, 15.0), corner_radius: 6.0 },
+                4 => HoverShape::AnnularSector {
+                    inner_radius: 10.0,
+                    outer_radius: 25.0,
+                    start_angle: 0.0,
+                    end_angle: std::f32::consts::PI,
+                },
+                _ => HoverShape::Triangle(Vec2::new(-20.0, -20.0), Vec2::new(20.0, -20.0), Vec2::new(0.0, 20.0)),
+            };
+            let x = (idx % side) as f32 * spacing - offset;
+            let y = (idx / side) as f32 * spacing - offset;
+            app.world.spawn((
+                TransformBundle::from_transform(
+                    Transform::from_xyz(x, y, 0.0).with_rotation(Quat::from_rotation_z(idx as f32 * 0.1)),
+                ),
+                MouseHoverTracker::from_shape(shape),
+            ));
+        }
+
+        // Let the transforms propagate and the grid build
+        for _ in 0..3 {
+            app.update();
+        }
+
+        let start = Instant::now();
+        for _ in 0..FRAMES {
+            app.update();
+        }
+        let still = start.elapsed() / FRAMES;
+
+        let start = Instant::now();
+        for frame in 0..FRAMES {
+            let t = frame as f32 / FRAMES as f32;
+            app.insert_resource(pointer_at(Vec2::from_angle(t * 20.0) * offset * t));
+            app.update();
+        }
+        let moving = start.elapsed() / FRAMES;
+
+        // Testing every tracker against its full transform, as hover tests
+        // used to
+        let mut trackers = app.world.query::<(&MouseHoverTracker, &GlobalTransform)>();
+        let start = Instant::now();
+        let mut hits = 0;
+        for frame in 0..FRAMES {
+            let t = frame as f32 / FRAMES as f32;
+            let mouse_pos = Vec2::from_angle(t * 20.0) * offset * t;
+            for (tracker, transform) in trackers.iter(&app.world) {
+                let local = transform.compute_matrix().inverse().transform_point3(mouse_pos.extend(0.0)).xy();
+                if tracker.shape.contains(local) {
+                    hits += 1;
+                }
+            }
+        }
+        let brute_force = start.elapsed() / FRAMES;
+
+        println!(
+            "{count} trackers: cursor still {still:?}, cursor moving {moving:?} per update, \
+            testing every tracker {brute_force:?} per frame ({hits} hits)"
+        );
+    }
+}
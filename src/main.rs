@@ -1,14 +1,22 @@
+use bevy::a11y::accesskit::{Live, NodeBuilder, Role};
+use bevy::a11y::{AccessibilityNode, Focus};
+use bevy::app::AppExit;
 use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology, VertexAttributeValues};
+use bevy::render::camera::NormalizedRenderTarget;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::view::RenderLayers;
 use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
-use bevy::window::PrimaryWindow;
-use bevy::ecs::system::SystemId;
+use bevy::window::{PrimaryWindow, WindowCloseRequested};
+use bevy::ecs::system::{EntityCommands, SystemId, SystemParam};
 use bevy::sprite::Anchor;
 
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use strum_macros::EnumIter;
 use strum::IntoEnumIterator;
-use std::collections::HashMap;
-
-use rand::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
 
 #[cfg(target_arch = "wasm32")]
 mod wasm {
@@ -32,6 +40,57 @@ mod wasm {
         let score_str = local_storage.get_item("high_score").expect("should be able to get localStorage item");
         score_str.unwrap_or_default().parse::<u8>().unwrap_or(0)
     }
+
+    pub fn save_item(key: &str, value: &str) {
+        let window = window().expect("should have a Window");
+        let local_storage = window.local_storage().expect("should have localStorage").expect("localStorage should be available");
+
+        local_storage.set_item(key, value).expect("should be able to set localStorage item");
+    }
+
+    pub fn load_item(key: &str) -> Option<String> {
+        let window = window().expect("should have a Window");
+        let local_storage = window.local_storage().expect("should have localStorage").expect("localStorage should be available");
+
+        local_storage.get_item(key).expect("should be able to get localStorage item")
+    }
+
+    pub fn prefers_reduced_motion() -> bool {
+        let window = window().expect("should have a Window");
+        window
+            .match_media("(prefers-reduced-motion: reduce)")
+            .ok()
+            .flatten()
+            .is_some_and(|query| query.matches())
+    }
+
+    /// Screen readers read out changes to an `aria-live` element, so keep a
+    /// visually hidden one around for announcements
+    pub fn announce(text: &str) {
+        let window = window().expect("should have a Window");
+        let document = window.document().expect("should have a Document");
+
+        let element = match document.get_element_by_id("announcer") {
+            Some(element) => element,
+            None => {
+                let element = document.create_element("div").expect("should be able to create an element");
+                element.set_id("announcer");
+                element.set_attribute("role", "status").expect("should be able to set role");
+                element.set_attribute("aria-live", "polite").expect("should be able to set aria-live");
+                element
+                    .set_attribute("style", "position:absolute;width:1px;height:1px;overflow:hidden;clip:rect(0 0 0 0);")
+                    .expect("should be able to set style");
+                document
+                    .body()
+                    .expect("should have a body")
+                    .append_child(&element)
+                    .expect("should be able to add the announcer");
+                element
+            }
+        };
+
+        element.set_text_content(Some(text));
+    }
 }
 
 fn save_score(score: u8) {
@@ -41,7 +100,7 @@ fn save_score(score: u8) {
     }
     #[cfg(not(target_arch = "wasm32"))]
     {
-          std::fs::write(SAVE_PATH, score.to_string()).unwrap()
+        std::fs::write(SAVE_PATH, score.to_string()).unwrap()
     }
 }
 
@@ -62,6 +121,30 @@ fn load_score() -> u8 {
     }
 }
 
+/// Save an arbitrary string under `key`. On desktop each key gets its own file
+/// next to `SAVE_PATH`, on WASM it's a `localStorage` item.
+fn save_item(key: &str, value: &str) {
+    #[cfg(target_arch = "wasm32")]
+    {
+        wasm::save_item(key, value)
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::fs::write(format!("local.{key}.data"), value).unwrap()
+    }
+}
+
+fn load_item(key: &str) -> Option<String> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        wasm::load_item(key)
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::fs::read_to_string(format!("local.{key}.data")).ok()
+    }
+}
+
 #[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, EnumIter)]
 enum Scene {
     Startup,
@@ -70,6 +153,10 @@ enum Scene {
     Game,
     Score,
     Credits,
+    ModeSelect,
+    PlayerSelect,
+    Settings,
+    Stats,
 }
 
 #[derive(Resource)]
@@ -77,12 +164,245 @@ struct SceneSetupSystem {
     system_map: HashMap<Scene, SystemId>
 }
 
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+enum GameMode {
+    Classic,
+    /// Race against the recorded best classic run
+    Ghost,
+    /// Pass-and-play for the given number of players
+    HotSeat(u8),
+    /// The players build the pattern, adding a pad after each replay
+    AddOne,
+    /// Like `AddOne`, but the pattern is never played back
+    AddOneStrict,
+    /// Input the pattern back to front
+    Reverse,
+    /// Input the pattern with each pad swapped for the one opposite it
+    Mirror,
+    /// Watch one `SPRINT_LENGTH` pad pattern, then replay it as fast as
+    /// possible
+    Sprint,
+    /// Playback gets faster every round
+    Rapid,
+}
+
+/// Modes that keep their own high score
+const RECORD_MODES: [GameMode; 6] = [
+    GameMode::Classic,
+    GameMode::AddOne,
+    GameMode::AddOneStrict,
+    GameMode::Reverse,
+    GameMode::Mirror,
+    GameMode::Rapid,
+];
+
+const SPRINT_LENGTH: u8 = 20;
+
+/// Round and press counts are `u8`s, so no pattern gets longer than this
+const MAX_PATTERN_LEN: u8 = 255;
+
+const PATTERN_STEP_SECONDS: f32 = 1.0;
+
+/// Rapid mode never plays back faster than this
+const RAPID_MIN_STEP_SECONDS: f32 = 0.25;
+
+/// How much shorter each step gets with every rapid round
+const RAPID_SPEED_UP: f32 = 0.9;
+
+/// Keeps flashing at or below three times a second, the usual photosensitive
+/// seizure threshold
+const REDUCED_FLASH_MIN_STEP_SECONDS: f32 = 0.34;
+
+/// The most the lightness of a pad may change when it lights up with
+/// reduced flashing
+const REDUCED_FLASH_MAX_LIGHTNESS_CHANGE: f32 = 0.15;
+
+fn rapid_step_seconds(round: u8, reduced_flash: bool) -> f32 {
+    let min_step = if reduced_flash {
+        REDUCED_FLASH_MIN_STEP_SECONDS
+    } else {
+        RAPID_MIN_STEP_SECONDS
+    };
+    (PATTERN_STEP_SECONDS * RAPID_SPEED_UP.powi(round as i32)).max(min_step)
+}
+
+/// Pull `lit` towards `base` so their lightness differs by at most `max_change`
+fn cap_lightness_change(base: Color, lit: Color, max_change: f32) -> Color {
+    let base_l = base.l();
+    let lit_l = lit.l().clamp(base_l - max_change, base_l + max_change);
+    let mut capped = lit.as_hsla();
+    capped.set_l(lit_l);
+    capped
+}
+
+impl GameMode {
+    fn input_transform(&self) -> InputTransform {
+        match self {
+            GameMode::Reverse => InputTransform::Reverse,
+            GameMode::Mirror => InputTransform::Mirror,
+            _ => InputTransform::Direct,
+        }
+    }
+
+    /// Name of the mode in saved games, for the modes that can be resumed
+    fn save_name(&self) -> Option<&'static str> {
+        match self {
+            GameMode::Classic => Some("classic"),
+            GameMode::AddOne => Some("add_one"),
+            GameMode::AddOneStrict => Some("add_one_strict"),
+            GameMode::Reverse => Some("reverse"),
+            GameMode::Mirror => Some("mirror"),
+            GameMode::Rapid => Some("rapid"),
+            GameMode::Ghost | GameMode::HotSeat(_) | GameMode::Sprint => None,
+        }
+    }
+
+    /// Storage key for the mode's high score. Classic predates the other
+    /// modes and keeps using `save_score`/`load_score`.
+    fn high_score_key(&self) -> Option<&'static str> {
+        match self {
+            GameMode::Classic | GameMode::Ghost | GameMode::HotSeat(_) | GameMode::Sprint => None,
+            GameMode::AddOne => Some("high_score_add_one"),
+            GameMode::AddOneStrict => Some("high_score_add_one_strict"),
+            GameMode::Reverse => Some("high_score_reverse"),
+            GameMode::Mirror => Some("high_score_mirror"),
+            GameMode::Rapid => Some("high_score_rapid"),
+        }
+    }
+}
+
+/// How the pad the player has to press relates to the pattern that was shown
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+enum InputTransform {
+    #[default]
+    Direct,
+    Reverse,
+    /// Top and bottom swap, as do left and right
+    Mirror,
+}
+
+impl InputTransform {
+    fn expected_pad(&self, round: &[u8], idx: usize) -> u8 {
+        match self {
+            InputTransform::Direct => round[idx],
+            InputTransform::Reverse => round[round.len() - 1 - idx],
+            // Pads are numbered clockwise, so the opposite pad is two along
+            InputTransform::Mirror => (round[idx] + 2) % 4,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct SelectedMode(GameMode);
+
+#[derive(Clone, Copy, Debug)]
+struct RecordedPress {
+    pad: u8,
+    /// Seconds since the player was allowed to start inputting the round
+    time: f32,
+}
+
+/// Every press made during a run, grouped by round. The final round holds the
+/// press that ended the run.
+#[derive(Clone, Default, Debug)]
+struct RunRecording {
+    pattern: Vec<u8>,
+    rounds: Vec<Vec<RecordedPress>>,
+}
+
+impl RunRecording {
+    fn score(&self) -> u8 {
+        self.rounds.len().saturating_sub(1) as u8
+    }
+
+    /// How long the round took to input, if it was completed
+    fn round_time(&self, round: usize) -> Option<f32> {
+        if round < self.score() as usize {
+            self.rounds[round].last().map(|press| press.time)
+        } else {
+            None
+        }
+    }
+
+    /// The first line is the pattern, followed by one line per round of
+    /// `pad:time` pairs.
+    fn to_save_string(&self) -> String {
+        let mut out: String = self.pattern.iter().map(|pad| pad.to_string()).collect();
+        for round in &self.rounds {
+            out.push('\n');
+            let presses: Vec<String> = round
+                .iter()
+                .map(|press| format!("{}:{:.3}", press.pad, press.time))
+                .collect();
+            out.push_str(&presses.join(" "));
+        }
+        out
+    }
+
+    fn from_save_string(s: &str) -> Option<Self> {
+        let mut lines = s.lines();
+        let pattern = lines
+            .next()?
+            .chars()
+            .map(|c| c.to_digit(4).map(|pad| pad as u8))
+            .collect::<Option<Vec<u8>>>()?;
+
+        let mut rounds = Vec::new();
+        for line in lines {
+            let mut presses = Vec::new();
+            for press in line.split_whitespace() {
+                let (pad, time) = press.split_once(':')?;
+                presses.push(RecordedPress {
+                    pad: pad.parse().ok()?,
+                    time: time.parse().ok()?,
+                });
+            }
+            rounds.push(presses);
+        }
+
+        if pattern.is_empty() || rounds.is_empty() {
+            return None;
+        }
+
+        Some(Self { pattern, rounds })
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct HotSeatPlayer {
+    /// The score the player had when they failed, `None` while still playing
+    eliminated: Option<u8>,
+}
+
 #[derive(Resource, Default)]
 struct GameState {
     pattern: Vec<u8>,
     interactive: bool,
     max_idx: u8,
     idx: u8,
+    /// Elapsed time when the current round started accepting input
+    round_start: f64,
+    rounds: Vec<Vec<RecordedPress>>,
+    /// Empty unless this is a hot seat game
+    players: Vec<HotSeatPlayer>,
+    current_player: usize,
+    /// The pattern starts empty and grows by a pad the player picks after
+    /// each replay
+    add_one: bool,
+    /// Go straight to input without playing the pattern back
+    skip_playback: bool,
+    input_transform: InputTransform,
+    /// The game is won once this many rounds are complete
+    length_limit: Option<u8>,
+    /// Shorten the playback step after every round
+    speed_up: bool,
+    /// Whether a pad is currently lit up by playback
+    playback_lit: bool,
+    /// Generates the random patterns, so a resumed game carries on with the
+    /// same one
+    seed: u64,
+    /// Continued from a saved game, so the earlier rounds weren't recorded
+    resumed: bool,
 }
 
 impl GameState {
@@ -90,851 +410,4227 @@ impl GameState {
         Self::default()
     }
 
-    fn reset(self: &mut Self) {
+    fn reset(&mut self) {
         *self = Self::default();
     }
+
+    fn expected_pad(&self) -> u8 {
+        let round = &self.pattern[..self.round_len()];
+        self.input_transform.expected_pad(round, self.idx as usize)
+    }
+
+    /// The number of pads played back and replayed this round
+    fn round_len(&self) -> usize {
+        if self.add_one {
+            self.pattern.len()
+        } else {
+            self.max_idx as usize + 1
+        }
+    }
+
+    /// Whether the next press adds a new pad instead of replaying one
+    fn awaiting_extension(&self) -> bool {
+        self.add_one && self.idx as usize == self.pattern.len()
+    }
+
+    fn players_remaining(&self) -> usize {
+        self.players.iter().filter(|player| player.eliminated.is_none()).count()
+    }
+
+    /// Pass the turn to the next player still in the game. Returns true when
+    /// the turn wraps around, meaning everyone left has played this round.
+    fn advance_player(&mut self) -> bool {
+        let count = self.players.len();
+        for offset in 1..=count {
+            let next = self.current_player + offset;
+            if self.players[next % count].eliminated.is_none() {
+                self.current_player = next % count;
+                return next >= count;
+            }
+        }
+        true
+    }
+
+    fn player_score(&self, player: usize) -> u8 {
+        self.players[player].eliminated.unwrap_or(self.max_idx)
+    }
+
+    fn memorize_text(&self) -> String {
+        if !self.players.is_empty() {
+            format!("Player {}: Memorize", self.current_player + 1)
+        } else if self.skip_playback {
+            "Get Ready".to_string()
+        } else {
+            "Memorize".to_string()
+        }
+    }
+
+    /// Total time spent inputting completed rounds
+    ///
+    /// A run that reached its length limit has no failed final round, so
+    /// every round counts.
+    fn input_time(&self) -> f32 {
+        let completed = if self.length_limit.is_some_and(|limit| self.max_idx >= limit) {
+            self.rounds.len()
+        } else {
+            self.recording().score() as usize
+        };
+        self.rounds[..completed]
+            .iter()
+            .filter_map(|round| round.last())
+            .map(|press| press.time)
+            .sum()
+    }
+
+    fn recording(&self) -> RunRecording {
+        RunRecording {
+            pattern: self.pattern.clone(),
+            rounds: self.rounds.clone(),
+        }
+    }
 }
 
-#[derive(Resource)]
-struct HighScore(u8);
+const BEST_RUN_KEY: &str = "best_run";
 
+/// The run that set the current high score, used as the ghost
 #[derive(Resource)]
-struct OldHighScore(u8);
+struct BestRun(Option<RunRecording>);
 
-#[derive(Resource)]
-struct CurrentScene(Scene);
+fn load_best_run() -> Option<RunRecording> {
+    load_item(BEST_RUN_KEY).and_then(|s| RunRecording::from_save_string(&s))
+}
+
+/// Index of the next ghost press to show in the current round
+#[derive(Resource, Default)]
+struct GhostCursor(usize);
 
 #[derive(Resource)]
-struct NextScene(Scene);
+struct HighScores(HashMap<GameMode, u8>);
 
-#[derive(Resource, Debug)]
-struct ShmMousePosition {
-    pos: Option<Vec2>,
+fn load_high_scores() -> HighScores {
+    let scores = RECORD_MODES
+        .iter()
+        .map(|&mode| {
+            let score = match mode.high_score_key() {
+                Some(key) => load_item(key).and_then(|s| s.parse().ok()).unwrap_or(0),
+                None => load_score(),
+            };
+            (mode, score)
+        })
+        .collect();
+    HighScores(scores)
 }
 
-#[derive(Resource)]
-struct PatternAnimationTimer(Timer);
+fn save_high_score(mode: GameMode, score: u8) {
+    match mode.high_score_key() {
+        Some(key) => save_item(key, &score.to_string()),
+        None => save_score(score),
+    }
+}
 
 #[derive(Resource)]
-struct PatternSounds(Handle<AudioSource>, Handle<AudioSource>, Handle<AudioSource>, Handle<AudioSource>);
+struct OldHighScore(u8);
 
-#[derive(Component)]
-struct PatternIdx(u8);
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default, EnumIter)]
+enum Palette {
+    #[default]
+    Standard,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+    HighContrast,
+}
 
-#[derive(Component)]
-struct SceneChangeButton {
-    width: f32,
-    height: f32,
-    scene: Scene,
+impl Palette {
+    fn name(&self) -> &'static str {
+        match self {
+            Palette::Standard => "Standard",
+            Palette::Deuteranopia => "Deuteranopia",
+            Palette::Protanopia => "Protanopia",
+            Palette::Tritanopia => "Tritanopia",
+            Palette::HighContrast => "High Contrast",
+        }
+    }
+
+    /// The resting and lit colours for a pad
+    fn pad_colors(&self, pad: u8) -> (Color, Color) {
+        match (self, pad) {
+            (Palette::Standard, 0) => (Color::hsl(0.0, 0.95, 0.9), Color::hsl(0.0, 0.95, 0.8)),
+            (Palette::Standard, 1) => (Color::hsl(115.0, 0.95, 0.9), Color::hsl(115.0, 0.95, 0.8)),
+            (Palette::Standard, 2) => (Color::hsl(235.0, 0.95, 0.9), Color::hsl(235.0, 0.95, 0.8)),
+            (Palette::Standard, _) => (Color::hsl(60.0, 0.95, 0.9), Color::hsl(60.0, 0.95, 0.8)),
+
+            // Red-green deficiencies: lean on the blue/orange axis and keep the
+            // pads at different lightness. Protanopes see red as dark, so
+            // they get orange rather than vermillion.
+            (Palette::Deuteranopia, 0) => (Color::rgb_u8(250, 200, 170), Color::rgb_u8(213, 94, 0)),
+            (Palette::Protanopia, 0) => (Color::rgb_u8(250, 225, 170), Color::rgb_u8(230, 159, 0)),
+            (Palette::Deuteranopia | Palette::Protanopia, 1) => (Color::rgb_u8(205, 232, 248), Color::rgb_u8(86, 180, 233)),
+            (Palette::Deuteranopia | Palette::Protanopia, 2) => (Color::rgb_u8(170, 200, 230), Color::rgb_u8(0, 114, 178)),
+            (Palette::Deuteranopia | Palette::Protanopia, _) => (Color::rgb_u8(250, 245, 185), Color::rgb_u8(240, 228, 66)),
+
+            // Blue-yellow deficiency: red, teal, pink and grey
+            (Palette::Tritanopia, 0) => (Color::rgb_u8(250, 200, 195), Color::rgb_u8(220, 50, 32)),
+            (Palette::Tritanopia, 1) => (Color::rgb_u8(190, 235, 230), Color::rgb_u8(0, 150, 136)),
+            (Palette::Tritanopia, 2) => (Color::rgb_u8(240, 210, 228), Color::rgb_u8(204, 121, 167)),
+            (Palette::Tritanopia, _) => (Color::rgb_u8(215, 215, 215), Color::rgb_u8(60, 60, 60)),
+
+            // Strong tints that stand apart from the pale background and
+            // each other, lighting up dark and saturated
+            (Palette::HighContrast, 0) => (Color::rgb_u8(255, 160, 160), Color::rgb_u8(200, 0, 0)),
+            (Palette::HighContrast, 1) => (Color::rgb_u8(140, 225, 140), Color::rgb_u8(0, 130, 0)),
+            (Palette::HighContrast, 2) => (Color::rgb_u8(160, 175, 255), Color::rgb_u8(0, 0, 200)),
+            (Palette::HighContrast, _) => (Color::rgb_u8(255, 220, 70), Color::rgb_u8(140, 120, 0)),
+        }
+    }
 }
 
-#[derive(Component)]
-struct SceneObject(());
+/// Extra markings on each pad so they can be told apart without colour
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default, EnumIter)]
+enum PadGlyphs {
+    #[default]
+    Off,
+    Shapes,
+    Numbers,
+}
 
-enum HoverShape {
-    Rectangle(Vec2),
-    Triangle(Vec2, Vec2, Vec2),
+impl PadGlyphs {
+    fn name(&self) -> &'static str {
+        match self {
+            PadGlyphs::Off => "Off",
+            PadGlyphs::Shapes => "Shapes",
+            PadGlyphs::Numbers => "Numbers",
+        }
+    }
 }
 
-#[derive(Component)]
-struct MouseHoverDisable;
+/// How the four pads are arranged on screen
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default, EnumIter)]
+enum BoardLayout {
+    /// Four triangles meeting in the middle of the window
+    #[default]
+    Triangles,
+    /// A classic round board with a hub in the middle
+    Round,
+}
 
-#[derive(Component)]
-struct MouseHoverTracker {
-    is_hovered: bool,
-    is_just_hovered: bool,
-    is_just_unhovered: bool,
-    shape: HoverShape
+impl BoardLayout {
+    fn name(&self) -> &'static str {
+        match self {
+            BoardLayout::Triangles => "Triangles",
+            BoardLayout::Round => "Round",
+        }
+    }
 }
 
-#[derive(Component)]
-struct MouseOverMaterial(Handle<ColorMaterial>);
+/// Step to the next variant of an enum, wrapping around at the end
+fn cycle<T: IntoEnumIterator + PartialEq + Copy>(current: T) -> T {
+    let variants: Vec<T> = T::iter().collect();
+    let idx = variants.iter().position(|&v| v == current).unwrap_or(0);
+    variants[(idx + 1) % variants.len()]
+}
 
-#[derive(Component)]
-struct MouseOutMaterial(Handle<ColorMaterial>);
+const SETTINGS_KEY: &str = "settings";
 
-#[derive(Component)]
-struct MainCamera;
+#[derive(Resource)]
+struct Settings {
+    palette: Palette,
+    glyphs: PadGlyphs,
+    /// Announce pads, presses and menu focus for screen readers
+    accessibility: bool,
+    /// Outline pads during playback instead of flashing them
+    reduced_flash: bool,
+    /// Percentage of each playback step that the pad is lit for
+    flash_duty: u8,
+    layout: BoardLayout,
+}
 
-#[derive(Component)]
-struct MemorizeLabel;
+const FLASH_DUTY_OPTIONS: [u8; 4] = [50, 75, 90, 100];
 
-impl MouseHoverTracker {
-    fn from_rect(w: f32, h: f32) -> Self {
+impl Default for Settings {
+    fn default() -> Self {
         Self {
-            shape: HoverShape::Rectangle(Vec2::new(w/2., h/2.)),
-            is_hovered: false,
-            is_just_hovered: false,
-            is_just_unhovered: false,
+            palette: Palette::default(),
+            glyphs: PadGlyphs::default(),
+            accessibility: false,
+            reduced_flash: false,
+            flash_duty: 75,
+            layout: BoardLayout::default(),
         }
     }
+}
 
-    fn from_triangle(a: Vec2, b: Vec2, c: Vec2) -> Self {
-        Self {
-            shape: HoverShape::Triangle(a, b, c),
-            is_hovered: false,
-            is_just_hovered: false,
-            is_just_unhovered: false,
-        }
+fn on_off(value: bool) -> &'static str {
+    if value { "On" } else { "Off" }
+}
+
+impl Settings {
+    /// One `name=value` pair per line
+    fn to_save_string(&self) -> String {
+        format!(
+            "palette={}\nglyphs={}\naccessibility={}\nreduced_flash={}\nflash_duty={}\nlayout={}",
+            self.palette.name(),
+            self.glyphs.name(),
+            on_off(self.accessibility),
+            on_off(self.reduced_flash),
+            self.flash_duty,
+            self.layout.name(),
+        )
     }
 
-    fn set_hovered(self: &mut Self, is_hovered: bool) {
-        if self.is_hovered != is_hovered {
-            self.is_hovered = is_hovered;
+    fn flash_duty_cycle(&self) -> f32 {
+        self.flash_duty as f32 / 100.0
+    }
 
-            self.is_just_hovered = false;
-            self.is_just_unhovered = false;
-            if is_hovered {
-                self.is_just_hovered = true;
-            } else {
-                self.is_just_unhovered = true;
+    /// Overrides the current settings with any that were saved
+    fn load_save_string(&mut self, s: &str) {
+        for line in s.lines() {
+            let Some((name, value)) = line.split_once('=') else {
+                continue;
+            };
+            match name {
+                "palette" => {
+                    if let Some(palette) = Palette::iter().find(|p| p.name() == value) {
+                        self.palette = palette;
+                    }
+                }
+                "glyphs" => {
+                    if let Some(glyphs) = PadGlyphs::iter().find(|g| g.name() == value) {
+                        self.glyphs = glyphs;
+                    }
+                }
+                "accessibility" => self.accessibility = value == on_off(true),
+                "reduced_flash" => self.reduced_flash = value == on_off(true),
+                "flash_duty" => {
+                    if let Ok(duty) = value.parse::<u8>() {
+                        self.flash_duty = duty.clamp(1, 100);
+                    }
+                }
+                "layout" => {
+                    if let Some(layout) = BoardLayout::iter().find(|l| l.name() == value) {
+                        self.layout = layout;
+                    }
+                }
+                _ => {}
             }
-        } else {
-            self.is_just_hovered = false;
-            self.is_just_unhovered = false;
         }
     }
+
+    fn save(&self) {
+        save_item(SETTINGS_KEY, &self.to_save_string());
+    }
 }
 
-fn check_collision_point_tri(p: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
-    // Get the barycentric coordinates and check that they're within the triangle
-    // TODO: Maybe this could be faster if the coordinates weren't normalized
-    // to the size of the triangle?
-    let inv_triangle_area = ((b.y - c.y) * (a.x - c.x) + (c.x - b.x) * (a.y - c.y)).recip();
+/// Until the player picks for themselves, follow the system preference
+fn default_settings() -> Settings {
+    Settings {
+        reduced_flash: prefers_reduced_motion(),
+        ..default()
+    }
+}
 
-    let bary_a_area = (b.y - c.y) * (p.x - c.x) + (c.x - b.x) * (p.y - c.y);
-    let bary_b_area = (c.y - a.y) * (p.x - c.x) + (a.x - c.x) * (p.y - c.y);
+fn load_settings() -> Settings {
+    let mut settings = default_settings();
+    if let Some(s) = load_item(SETTINGS_KEY) {
+        settings.load_save_string(&s);
+    }
+    settings
+}
 
-    let bary_a = bary_a_area * inv_triangle_area;
-    let bary_b = bary_b_area * inv_triangle_area;
-    let bary_c = 1. - bary_a - bary_b;
+fn prefers_reduced_motion() -> bool {
+    #[cfg(target_arch = "wasm32")]
+    {
+        wasm::prefers_reduced_motion()
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        false
+    }
+}
 
-    (bary_a > 0.) && (bary_b > 0.) && (bary_c > 0.)
+const SAVED_GAME_KEY: &str = "saved_game";
+
+/// A single player game that was interrupted, as of the start of its
+/// current round
+struct SavedGame {
+    mode: GameMode,
+    seed: u64,
+    max_idx: u8,
+    /// The pattern as far as the current round
+    pattern: Vec<u8>,
 }
 
-fn setup(
-    world: &mut World,
-) {
-    world.spawn((Camera2dBundle::default(), MainCamera));
+impl SavedGame {
+    /// None for modes that can't be resumed
+    fn new(mode: GameMode, state: &GameState) -> Option<Self> {
+        mode.save_name()?;
+        Some(Self {
+            mode,
+            seed: state.seed,
+            max_idx: state.max_idx,
+            pattern: state.pattern[..state.round_len()].to_vec(),
+        })
+    }
 
-    let mut system_map = HashMap::new();
-    for scene in Scene::iter() {
-        if let Some(system_id) = match scene {
-            Scene::Startup => None,
-            Scene::ClickToStart => Some(world.register_system(setup_click_to_start_scene)),
-            Scene::MainMenu => Some(world.register_system(setup_main_menu)),
-            Scene::Credits => Some(world.register_system(setup_credits)),
-            Scene::Game => Some(world.register_system(setup_game)),
-            Scene::Score => Some(world.register_system(setup_score)),
-        } {
-            system_map.insert(scene, system_id);
+    /// One `name=value` pair per line
+    fn to_save_string(&self) -> String {
+        let pattern: String = self.pattern.iter().map(|pad| pad.to_string()).collect();
+        format!(
+            "mode={}\nseed={}\nmax_idx={}\npattern={}",
+            self.mode.save_name().unwrap_or_default(),
+            self.seed,
+            self.max_idx,
+            pattern,
+        )
+    }
+
+    fn from_save_string(s: &str) -> Option<Self> {
+        let mut mode = None;
+        let mut seed = None;
+        let mut max_idx = None;
+        let mut pattern = None;
+        for line in s.lines() {
+            let Some((name, value)) = line.split_once('=') else {
+                continue;
+            };
+            match name {
+                "mode" => mode = RECORD_MODES.into_iter().find(|mode| mode.save_name() == Some(value)),
+                "seed" => seed = value.parse().ok(),
+                "max_idx" => max_idx = value.parse().ok(),
+                "pattern" => {
+                    pattern = value
+                        .chars()
+                        .map(|c| c.to_digit(4).map(|pad| pad as u8))
+                        .collect::<Option<Vec<u8>>>();
+                }
+                _ => {}
+            }
         }
+        let saved = Self {
+            mode: mode?,
+            seed: seed?,
+            max_idx: max_idx?,
+            pattern: pattern?,
+        };
+
+        // The pattern has to be exactly what `new` keeps for the round, or
+        // resuming would index past it
+        let add_one = matches!(saved.mode, GameMode::AddOne | GameMode::AddOneStrict);
+        let round_len = saved.max_idx as usize + if add_one { 0 } else { 1 };
+        (saved.max_idx < MAX_PATTERN_LEN && saved.pattern.len() == round_len).then_some(saved)
     }
+}
 
-    let setup_systems = SceneSetupSystem { system_map };
-    world.insert_resource(setup_systems);
+/// The saved game offered by Continue in the main menu
+#[derive(Resource)]
+struct ResumableGame {
+    saved: Option<SavedGame>,
+    /// The next game starts from `saved` instead of from scratch
+    resume_next: bool,
 }
 
-fn load_assets(
-    asset_server: Res<AssetServer>,
-    mut commands: Commands,
-) {
-    commands.insert_resource(
-        PatternSounds(
-            asset_server.load("sounds/drop_003_p0.ogg"),
-            asset_server.load("sounds/drop_003_p1.ogg"),
-            asset_server.load("sounds/drop_003_p2.ogg"),
-            asset_server.load("sounds/drop_003_p3.ogg"),
+impl ResumableGame {
+    fn save(&mut self, mode: GameMode, state: &GameState) {
+        if let Some(saved) = SavedGame::new(mode, state) {
+            save_item(SAVED_GAME_KEY, &saved.to_save_string());
+            self.saved = Some(saved);
+        }
+    }
+
+    /// Called when a game ends. Only a saved game of the same mode is
+    /// cleared, so losing in one mode doesn't lose the save from another.
+    fn clear(&mut self, mode: GameMode) {
+        if self.saved.as_ref().is_some_and(|saved| saved.mode == mode) {
+            self.saved = None;
+            save_item(SAVED_GAME_KEY, "");
+        }
+    }
+}
+
+fn load_resumable_game() -> ResumableGame {
+    ResumableGame {
+        saved: load_item(SAVED_GAME_KEY).and_then(|s| SavedGame::from_save_string(&s)),
+        resume_next: false,
+    }
+}
+
+const SPRINT_RECORD_KEY: &str = "sprint_record";
+
+/// Fastest completed sprint, in milliseconds
+#[derive(Resource)]
+struct SprintRecord(Option<u32>);
+
+fn load_sprint_record() -> Option<u32> {
+    load_item(SPRINT_RECORD_KEY).and_then(|s| s.parse().ok())
+}
+
+const STATS_KEY: &str = "stats";
+
+/// Totals across every single player game on this device
+#[derive(Resource, Default)]
+struct LifetimeStats {
+    /// How many games ended on each score
+    score_counts: BTreeMap<u8, u32>,
+    pads_pressed: u32,
+    correct_presses: u32,
+    /// How many games ended on a wrong press when each pad was expected
+    misses: [u32; 4],
+    /// Longest the app has been open by the end of a game
+    longest_session_seconds: u32,
+}
+
+impl LifetimeStats {
+    fn record_game(&mut self, state: &GameState, missed_pad: Option<u8>, session_seconds: u32) {
+        *self.score_counts.entry(state.max_idx).or_default() += 1;
+
+        // The recorded rounds include the wrong press that ended the game
+        let presses = state.rounds.iter().map(|round| round.len() as u32).sum::<u32>();
+        self.pads_pressed += presses;
+        self.correct_presses += presses.saturating_sub(missed_pad.is_some() as u32);
+        if let Some(pad) = missed_pad {
+            self.misses[pad as usize] += 1;
+        }
+        self.longest_session_seconds = self.longest_session_seconds.max(session_seconds);
+    }
+
+    fn games_played(&self) -> u32 {
+        self.score_counts.values().sum()
+    }
+
+    fn average_score(&self) -> Option<f32> {
+        let total: u32 = self.score_counts.iter().map(|(&score, &count)| score as u32 * count).sum();
+        let games = self.games_played();
+        (games > 0).then(|| total as f32 / games as f32)
+    }
+
+    /// Averages the middle two scores when there's an even number of games
+    fn median_score(&self) -> Option<f32> {
+        let games = self.games_played();
+        if games == 0 {
+            return None;
+        }
+        let nth_score = |n: u32| {
+            let mut seen = 0;
+            for (&score, &count) in &self.score_counts {
+                seen += count;
+                if seen > n {
+                    return score as f32;
+                }
+            }
+            0.0
+        };
+        Some((nth_score((games - 1) / 2) + nth_score(games / 2)) / 2.0)
+    }
+
+    fn accuracy(&self) -> Option<f32> {
+        (self.pads_pressed > 0).then(|| self.correct_presses as f32 / self.pads_pressed as f32)
+    }
+
+    /// Games counted into at most `HISTOGRAM_BUCKETS` equal score ranges,
+    /// along with how many scores each range covers
+    fn score_buckets(&self) -> (u32, Vec<u32>) {
+        let max_score = self.score_counts.keys().last().copied().unwrap_or_default() as u32;
+        let bucket_size = max_score / HISTOGRAM_BUCKETS + 1;
+        let bucket_count = max_score / bucket_size + 1;
+
+        let mut buckets = vec![0; bucket_count as usize];
+        for (&score, &count) in &self.score_counts {
+            buckets[(score as u32 / bucket_size) as usize] += count;
+        }
+        (bucket_size, buckets)
+    }
+
+    fn most_missed_pad(&self) -> Option<u8> {
+        (0..4u8)
+            .filter(|&pad| self.misses[pad as usize] > 0)
+            .max_by_key(|&pad| self.misses[pad as usize])
+    }
+
+    /// One `name=value` pair per line, with lists separated by commas
+    fn to_save_string(&self) -> String {
+        let scores: Vec<String> = self.score_counts.iter().map(|(score, count)| format!("{score}:{count}")).collect();
+        let misses: Vec<String> = self.misses.iter().map(|count| count.to_string()).collect();
+        format!(
+            "scores={}\npads_pressed={}\ncorrect_presses={}\nmisses={}\nlongest_session={}",
+            scores.join(","),
+            self.pads_pressed,
+            self.correct_presses,
+            misses.join(","),
+            self.longest_session_seconds,
         )
-    );
+    }
+
+    /// Overrides the current stats with any that were saved
+    fn load_save_string(&mut self, s: &str) {
+        for line in s.lines() {
+            let Some((name, value)) = line.split_once('=') else {
+                continue;
+            };
+            match name {
+                "scores" => {
+                    self.score_counts = value
+                        .split(',')
+                        .filter_map(|entry| {
+                            let (score, count) = entry.split_once(':')?;
+                            Some((score.parse().ok()?, count.parse().ok()?))
+                        })
+                        .collect();
+                }
+                "pads_pressed" => self.pads_pressed = value.parse().unwrap_or_default(),
+                "correct_presses" => self.correct_presses = value.parse().unwrap_or_default(),
+                "misses" => {
+                    for (count, saved) in self.misses.iter_mut().zip(value.split(',')) {
+                        *count = saved.parse().unwrap_or_default();
+                    }
+                }
+                "longest_session" => self.longest_session_seconds = value.parse().unwrap_or_default(),
+                _ => {}
+            }
+        }
+    }
+
+    fn save(&self) {
+        save_item(STATS_KEY, &self.to_save_string());
+    }
+}
+
+fn load_stats() -> LifetimeStats {
+    let mut stats = LifetimeStats::default();
+    if let Some(s) = load_item(STATS_KEY) {
+        stats.load_save_string(&s);
+    }
+    stats
+}
+
+/// Everything saved when a round or a game ends
+#[derive(SystemParam)]
+struct GameRecords<'w> {
+    resumable: ResMut<'w, ResumableGame>,
+    stats: ResMut<'w, LifetimeStats>,
+    real_time: Res<'w, Time<Real>>,
+}
+
+impl GameRecords<'_> {
+    fn round_complete(&mut self, mode: GameMode, state: &GameState) {
+        self.resumable.save(mode, state);
+    }
+
+    /// Hot seat games are shared between players so they're left out of the
+    /// stats
+    fn game_over(&mut self, mode: GameMode, state: &GameState, missed_pad: Option<u8>) {
+        self.resumable.clear(mode);
+        if state.players.is_empty() {
+            let session_seconds = self.real_time.elapsed().as_secs() as u32;
+            self.stats.record_game(state, missed_pad, session_seconds);
+            self.stats.save();
+        }
+    }
+}
+
+#[derive(Resource)]
+struct CurrentScene(Scene);
+
+#[derive(Resource)]
+struct NextScene(Scene);
+
+/// Where the cursor is for one camera whose viewport it's over
+#[derive(Debug, PartialEq)]
+pub struct CameraPointer {
+    pub camera: Entity,
+    /// Higher orders are drawn on top
+    pub order: isize,
+    /// Layers the camera sees. A camera that only draws UI can be given no
+    /// layers so nothing in the world is hovered through it.
+    pub layers: RenderLayers,
+    pub world_position: Vec2,
+}
+
+/// The cursor mapped through every active camera it's over, across all
+/// windows. Split-screen cameras each get their own pointer while the cursor
+/// is inside their viewport.
+#[derive(Resource, Debug, Default, PartialEq)]
+pub struct PointerPositions {
+    /// Topmost camera first
+    pub pointers: Vec<CameraPointer>,
+}
+
+impl PointerPositions {
+    /// World position under the cursor for something on the given layers,
+    /// through the topmost camera that sees it
+    fn world_position(&self, layers: &RenderLayers) -> Option<Vec2> {
+        self.pointers
+            .iter()
+            .find(|pointer| pointer.layers.intersects(layers))
+            .map(|pointer| pointer.world_position)
+    }
+
+    /// Where the cursor is in a tracker's local space
+    fn local_position(&self, cache: &HoverCache, layers: Option<&RenderLayers>) -> Vec2 {
+        self.world_position(&layers.copied().unwrap_or_default())
+            .map(|pos| cache.local_position(pos))
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Resource)]
+struct PatternAnimationTimer(Timer);
+
+#[derive(Resource)]
+struct PatternSounds(Handle<AudioSource>, Handle<AudioSource>, Handle<AudioSource>, Handle<AudioSource>);
+
+#[derive(Component)]
+struct PatternIdx(u8);
+
+/// Outline shown around a pad during playback when flashing is reduced
+#[derive(Component)]
+struct PadBorder(u8);
+
+/// Sent when the player presses a pad during their turn
+#[derive(Event)]
+struct PadPressed {
+    pad: u8,
+    /// Where the pad was pressed in its local space, if it was with the mouse
+    position: Option<Vec2>,
+    correct: bool,
+}
+
+/// Shrinks the pad in and back out when it's pressed
+#[derive(Component)]
+struct PadPress {
+    timer: Timer,
+    center: Vec2,
+}
+
+/// Shakes a wrongly pressed pad and tints it red
+#[derive(Component)]
+struct PadShake {
+    timer: Timer,
+    error_material: Handle<ColorMaterial>,
+}
+
+#[derive(Component)]
+struct Particle {
+    velocity: Vec2,
+    lifetime: Timer,
+}
+
+/// Counts down to the score screen once the game has been lost
+#[derive(Resource, Default)]
+struct PendingGameOver(Option<Timer>);
+
+/// Easing curves for tweens, mapping linear progress in 0..=1 to eased progress
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Ease {
+    QuadIn,
+    QuadOut,
+    CubicOut,
+    /// Overshoots slightly before settling, for things popping into place
+    BackOut,
+}
+
+impl Ease {
+    fn apply(&self, t: f32) -> f32 {
+        match self {
+            Ease::QuadIn => t * t,
+            Ease::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Ease::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Ease::BackOut => {
+                const OVERSHOOT: f32 = 1.70158;
+                let u = t - 1.0;
+                1.0 + (OVERSHOOT + 1.0) * u.powi(3) + OVERSHOOT * u.powi(2)
+            }
+        }
+    }
+}
+
+/// A property a tween animates
+///
+/// Colour and alpha apply to every section of an entity's `Text`, and to its
+/// `ColorMaterial` asset, which changes every entity sharing that material.
+#[derive(Clone, Copy, Debug)]
+enum TweenTarget {
+    Translation { start: Vec3, end: Vec3 },
+    Scale { start: Vec3, end: Vec3 },
+    Color { start: Color, end: Color },
+    Alpha { start: f32, end: f32 },
+}
+
+/// Animates one or more properties of an entity together, removing itself and
+/// sending `TweenCompleted` when it's done
+///
+/// Inserting a new tween replaces any tween already running on the entity.
+#[derive(Component)]
+struct Tween {
+    targets: Vec<TweenTarget>,
+    ease: Ease,
+    delay: f32,
+    duration: f32,
+    elapsed: f32,
+}
+
+impl Tween {
+    fn new(seconds: f32, ease: Ease) -> Self {
+        Self {
+            targets: Vec::new(),
+            ease,
+            delay: 0.0,
+            duration: seconds,
+            elapsed: 0.0,
+        }
+    }
+
+    fn with(mut self, target: TweenTarget) -> Self {
+        self.targets.push(target);
+        self
+    }
+
+    /// Holds the tween at its start values for a while, for staggering
+    fn with_delay(mut self, seconds: f32) -> Self {
+        self.delay = seconds;
+        self
+    }
+
+    fn fade_in(seconds: f32) -> Self {
+        Self::new(seconds, Ease::QuadOut).with(TweenTarget::Alpha { start: 0.0, end: 1.0 })
+    }
+
+    fn fade_out(seconds: f32) -> Self {
+        Self::new(seconds, Ease::QuadIn).with(TweenTarget::Alpha { start: 1.0, end: 0.0 })
+    }
+
+    /// Eased progress through the tween, from 0 until the delay is over to 1
+    /// when finished
+    fn progress(&self) -> f32 {
+        if self.duration <= 0.0 {
+            return 1.0;
+        }
+        self.ease.apply(((self.elapsed - self.delay) / self.duration).clamp(0.0, 1.0))
+    }
+
+    fn finished(&self) -> bool {
+        self.elapsed >= self.delay + self.duration
+    }
+}
+
+#[derive(Event)]
+struct TweenCompleted {
+    entity: Entity,
+}
+
+/// What a button does when it's activated
+#[derive(Component, Clone, Copy, Debug)]
+enum ButtonAction {
+    ChangeScene(Scene),
+    /// Sets the mode, along with any parameters it carries, and starts a game
+    StartMode(GameMode),
+    /// Moves the setting on to its next value
+    ToggleSetting(Setting),
+    /// Runs a one-shot system from `ButtonSystems`
+    RunSystem(SystemId),
+    Quit,
+}
+
+/// One-shot systems that buttons can run
+#[derive(Resource)]
+struct ButtonSystems {
+    reset_settings: SystemId,
+    resume_game: SystemId,
+    continue_game: SystemId,
+}
+
+/// Styling and click state for a Bevy UI button
+#[derive(Component)]
+struct MenuButton {
+    color: Color,
+    hover_color: Color,
+    pressed_color: Color,
+    /// Set while a press that started on this button is held, so releasing
+    /// over it counts as a click
+    armed: bool,
+}
+
+/// Greys out a button and stops it from being clicked or focused
+#[derive(Component)]
+struct DisabledButton;
+
+/// Sent when a button is clicked, or activated from the keyboard while
+/// focused. The button's `ButtonAction` decides what happens.
+#[derive(Event)]
+struct ButtonActivated(Entity);
+
+#[derive(Clone, Copy, Debug)]
+enum Setting {
+    Palette,
+    Glyphs,
+    Accessibility,
+    ReducedFlash,
+    FlashDuty,
+    Layout,
+}
+
+impl Setting {
+    fn label(&self, settings: &Settings) -> String {
+        match self {
+            Setting::Palette => format!("Colours: {}", settings.palette.name()),
+            Setting::Glyphs => format!("Symbols: {}", settings.glyphs.name()),
+            Setting::Accessibility => format!("Accessibility: {}", on_off(settings.accessibility)),
+            Setting::ReducedFlash => format!("Reduced Flashing: {}", on_off(settings.reduced_flash)),
+            Setting::FlashDuty => format!("Flash Length: {}%", settings.flash_duty),
+            Setting::Layout => format!("Board: {}", settings.layout.name()),
+        }
+    }
+
+    fn cycle(&self, settings: &mut Settings) {
+        match self {
+            Setting::Palette => settings.palette = cycle(settings.palette),
+            Setting::Glyphs => settings.glyphs = cycle(settings.glyphs),
+            Setting::Accessibility => settings.accessibility = !settings.accessibility,
+            Setting::ReducedFlash => settings.reduced_flash = !settings.reduced_flash,
+            Setting::FlashDuty => {
+                let next = FLASH_DUTY_OPTIONS.iter().position(|&duty| duty > settings.flash_duty).unwrap_or(0);
+                settings.flash_duty = FLASH_DUTY_OPTIONS[next];
+            }
+            Setting::Layout => settings.layout = cycle(settings.layout),
+        }
+    }
+}
+
+#[derive(Component)]
+struct SceneObject(());
+
+/// Whether the game is paused. Game time stops along with it, and the game
+/// systems don't run.
+#[derive(Resource, Default)]
+struct Paused(bool);
+
+/// The pause menu overlay, and the tracker that stops clicks reaching the
+/// pads under it
+#[derive(Component)]
+struct PauseMenu;
+
+/// Asks the app to save anything outstanding and exit
+#[derive(Event)]
+struct QuitRequested;
+
+/// A fading marker showing where the ghost pressed
+#[derive(Component)]
+struct GhostFlash(Timer);
+
+/// Area a `MouseHoverTracker` responds to, in the entity's local space.
+/// Shapes without their own points are centered on the origin.
+pub enum HoverShape {
+    /// Half the width and height
+    Rectangle(Vec2),
+    Triangle(Vec2, Vec2, Vec2),
+    /// Radius
+    Circle(f32),
+    /// Outline of a simple polygon, which may be concave
+    Polygon(Vec<Vec2>),
+    RoundedRectangle { half_size: Vec2, corner_radius: f32 },
+    /// Part of a ring, running counterclockwise from `start_angle` to
+    /// `end_angle` in radians, with zero along the positive x axis
+    AnnularSector { inner_radius: f32, outer_radius: f32, start_angle: f32, end_angle: f32 },
+    /// Any number of triangles, as read from a mesh
+    Triangles(Vec<[Vec2; 3]>),
+}
+
+impl HoverShape {
+    fn center(&self) -> Vec2 {
+        match self {
+            HoverShape::Rectangle(_)
+            | HoverShape::Circle(_)
+            | HoverShape::RoundedRectangle { .. } => Vec2::ZERO,
+            HoverShape::Triangle(a, b, c) => (*a + *b + *c) / 3.,
+            HoverShape::Polygon(points) => polygon_centroid(points),
+            HoverShape::AnnularSector { inner_radius, outer_radius, start_angle, end_angle } => {
+                Vec2::from_angle((start_angle + end_angle) / 2.) * (inner_radius + outer_radius) / 2.
+            }
+            HoverShape::Triangles(triangles) => triangles_centroid(triangles),
+        }
+    }
+
+    pub fn contains(&self, p: Vec2) -> bool {
+        match self {
+            HoverShape::Rectangle(r) => {
+                -r.x <= p.x && p.x <= r.x
+                    && -r.y <= p.y && p.y <= r.y
+            }
+            HoverShape::Triangle(a, b, c) => check_collision_point_tri(p, *a, *b, *c),
+            HoverShape::Circle(radius) => check_collision_point_circle(p, *radius),
+            HoverShape::Polygon(points) => check_collision_point_polygon(p, points),
+            HoverShape::RoundedRectangle { half_size, corner_radius } => {
+                check_collision_point_rounded_rect(p, *half_size, *corner_radius)
+            }
+            HoverShape::AnnularSector { inner_radius, outer_radius, start_angle, end_angle } => {
+                check_collision_point_annular_sector(p, *inner_radius, *outer_radius, *start_angle, *end_angle)
+            }
+            HoverShape::Triangles(triangles) => triangles
+                .iter()
+                .any(|[a, b, c]| check_collision_point_tri(p, *a, *b, *c)),
+        }
+    }
+
+    /// Box around the shape in local space
+    fn local_bounds(&self) -> Rect {
+        match self {
+            HoverShape::Rectangle(half_size)
+            | HoverShape::RoundedRectangle { half_size, .. } => Rect::from_center_half_size(Vec2::ZERO, *half_size),
+            HoverShape::Triangle(a, b, c) => points_bounds([a, b, c]),
+            HoverShape::Circle(radius)
+            | HoverShape::AnnularSector { outer_radius: radius, .. } => {
+                Rect::from_center_half_size(Vec2::ZERO, Vec2::splat(*radius))
+            }
+            HoverShape::Polygon(points) => points_bounds(points),
+            HoverShape::Triangles(triangles) => points_bounds(triangles.iter().flatten()),
+        }
+    }
+
+    /// Reads the triangles out of a 2d mesh. Returns `None` if the mesh isn't
+    /// a triangle list or has no positions.
+    fn from_mesh(mesh: &Mesh) -> Option<Self> {
+        if mesh.primitive_topology() != PrimitiveTopology::TriangleList {
+            return None;
+        }
+        let Some(VertexAttributeValues::Float32x3(positions)) = mesh.attribute(Mesh::ATTRIBUTE_POSITION) else {
+            return None;
+        };
+
+        let vertices: Vec<Vec2> = match mesh.indices() {
+            Some(indices) => indices.iter().map(|idx| Vec3::from(positions[idx]).xy()).collect(),
+            None => positions.iter().map(|position| Vec3::from(*position).xy()).collect(),
+        };
+        let mut triangles: Vec<[Vec2; 3]> = vertices
+            .chunks_exact(3)
+            .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+            .collect();
+
+        match triangles.len() {
+            0 => None,
+            1 => {
+                let [a, b, c] = triangles.remove(0);
+                Some(HoverShape::Triangle(a, b, c))
+            }
+            _ => Some(HoverShape::Triangles(triangles)),
+        }
+    }
+}
+
+fn points_bounds<'a>(points: impl IntoIterator<Item = &'a Vec2>) -> Rect {
+    let mut bounds = Rect { min: Vec2::INFINITY, max: Vec2::NEG_INFINITY };
+    for point in points {
+        bounds.min = bounds.min.min(*point);
+        bounds.max = bounds.max.max(*point);
+    }
+    bounds
+}
+
+/// Center of a polygon by the shoelace formula. Edges that double back on
+/// a concave polygon subtract their area, so this holds for any simple
+/// polygon.
+fn polygon_centroid(points: &[Vec2]) -> Vec2 {
+    let mut twice_area = 0.;
+    let mut weighted = Vec2::ZERO;
+    for (idx, &a) in points.iter().enumerate() {
+        let b = points[(idx + 1) % points.len()];
+        let cross = a.perp_dot(b);
+        twice_area += cross;
+        weighted += (a + b) * cross;
+    }
+    if twice_area == 0. {
+        Vec2::ZERO
+    } else {
+        weighted / (3. * twice_area)
+    }
+}
+
+/// Area weighted center of a set of separate triangles, like a mesh
+fn triangles_centroid(triangles: &[[Vec2; 3]]) -> Vec2 {
+    let mut total_area = 0.;
+    let mut weighted = Vec2::ZERO;
+    for [a, b, c] in triangles {
+        // Meshes can mix windings, so signed areas would cancel out
+        let area = (*b - *a).perp_dot(*c - *a).abs() / 2.;
+        total_area += area;
+        weighted += (*a + *b + *c) / 3. * area;
+    }
+    if total_area == 0. {
+        Vec2::ZERO
+    } else {
+        weighted / total_area
+    }
+}
+
+#[derive(Component)]
+pub struct MouseHoverDisable;
+
+#[derive(Component)]
+pub struct MouseHoverTracker {
+    is_hovered: bool,
+    pub shape: HoverShape,
+    /// Trackers with a higher priority are picked first, then the one with
+    /// the highest Z
+    priority: i32,
+    /// Lets trackers underneath this one be hovered as well
+    pass_through: bool,
+    /// Set when the mouse button went down over this tracker, and kept until
+    /// it's released even if the cursor is dragged away
+    is_pressed: bool,
+}
+
+/// World space data for hover testing a tracker, only recomputed when the
+/// tracker moves. Trackers' shapes are expected to stay the same once spawned.
+#[derive(Component)]
+pub struct HoverCache {
+    /// World to local space
+    inverse: Mat4,
+    /// World space box around the shape
+    bounds: Rect,
+    z: f32,
+}
+
+impl HoverCache {
+    fn new(tracker: &MouseHoverTracker, transform: &GlobalTransform) -> Self {
+        let local = tracker.shape.local_bounds();
+        let corners = [
+            local.min,
+            Vec2::new(local.min.x, local.max.y),
+            local.max,
+            Vec2::new(local.max.x, local.min.y),
+        ].map(|corner| transform.transform_point(corner.extend(0.0)).xy());
+        Self {
+            inverse: transform.compute_matrix().inverse(),
+            bounds: points_bounds(&corners),
+            z: transform.translation().z,
+        }
+    }
+
+    fn local_position(&self, world_position: Vec2) -> Vec2 {
+        self.inverse.transform_point3(world_position.extend(0.0)).xy()
+    }
+}
+
+const HOVER_GRID_CELL_SIZE: f32 = 128.0;
+
+/// Trackers covering more cells than this, like the click anywhere areas,
+/// are tested everywhere instead of being added to every cell
+const HOVER_GRID_MAX_CELLS: i32 = 64;
+
+/// Coarse grid of which trackers might be under each point, so hover tests
+/// only look at the trackers nearby
+#[derive(Resource, Default)]
+pub struct HoverGrid {
+    cells: HashMap<IVec2, Vec<Entity>>,
+    everywhere: Vec<Entity>,
+}
+
+impl HoverGrid {
+    fn cell(position: Vec2) -> IVec2 {
+        (position / HOVER_GRID_CELL_SIZE).floor().as_ivec2()
+    }
+
+    fn insert(&mut self, entity: Entity, bounds: Rect) {
+        if bounds.is_empty() || !bounds.min.is_finite() || !bounds.max.is_finite() {
+            self.everywhere.push(entity);
+            return;
+        }
+        let min = Self::cell(bounds.min);
+        let max = Self::cell(bounds.max);
+        let size = max - min + IVec2::ONE;
+        if size.x.saturating_mul(size.y) > HOVER_GRID_MAX_CELLS {
+            self.everywhere.push(entity);
+            return;
+        }
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                self.cells.entry(IVec2::new(x, y)).or_default().push(entity);
+            }
+        }
+    }
+
+    fn candidates(&self, position: Vec2) -> impl Iterator<Item = Entity> + '_ {
+        self.cells
+            .get(&Self::cell(position))
+            .into_iter()
+            .flatten()
+            .chain(self.everywhere.iter())
+            .copied()
+    }
+}
+
+/// The cursor moved onto a tracker. The position is in the tracker's local
+/// space.
+#[derive(Event)]
+pub struct HoverEnter {
+    entity: Entity,
+    // The current scenes only need to know which tracker was entered
+    #[allow(dead_code)]
+    position: Vec2,
+}
+
+/// The cursor left a tracker, or it stopped being hoverable
+#[derive(Event)]
+pub struct HoverExit {
+    entity: Entity,
+}
+
+/// The mouse button went down over a tracker. The position is in the
+/// tracker's local space.
+#[derive(Event)]
+struct Pressed {
+    entity: Entity,
+    // The current scenes only need to know which tracker was pressed
+    #[allow(dead_code)]
+    position: Vec2,
+}
+
+/// The mouse button was released over the same tracker it was pressed on.
+/// The position is in the tracker's local space.
+#[derive(Event)]
+struct Clicked {
+    entity: Entity,
+    position: Vec2,
+}
+
+#[derive(Component)]
+struct MouseOverMaterial(Handle<ColorMaterial>);
+
+#[derive(Component)]
+struct MouseOutMaterial(Handle<ColorMaterial>);
+
+/// Shown while a tracker is held down
+#[derive(Component)]
+struct MousePressedMaterial(Handle<ColorMaterial>);
+
+/// How much darker a pressed pad is than a hovered one
+const PRESSED_DARKEN: f32 = 0.8;
+
+#[derive(Component)]
+struct MainCamera;
+
+/// Text to be read out in accessibility mode
+#[derive(Event)]
+struct Announce(String);
+
+/// Live region that screen readers watch for announcements
+#[derive(Component)]
+struct Announcer;
+
+/// Pads are named by position since their colours depend on the palette
+const PAD_NAMES: [&str; 4] = ["Top", "Right", "Bottom", "Left"];
+
+fn pad_key(keys: &ButtonInput<KeyCode>) -> Option<u8> {
+    let pad_keys = [
+        [KeyCode::ArrowUp, KeyCode::KeyW, KeyCode::Digit1],
+        [KeyCode::ArrowRight, KeyCode::KeyD, KeyCode::Digit2],
+        [KeyCode::ArrowDown, KeyCode::KeyS, KeyCode::Digit3],
+        [KeyCode::ArrowLeft, KeyCode::KeyA, KeyCode::Digit4],
+    ];
+    pad_keys
+        .iter()
+        .position(|keys_for_pad| keys.any_just_pressed(*keys_for_pad))
+        .map(|pad| pad as u8)
+}
+
+/// Enter or space activates the focused button
+fn keyboard_activated(keys: &ButtonInput<KeyCode>, focus: &Focus, entity: Entity) -> bool {
+    focus.0 == Some(entity) && keys.any_just_pressed([KeyCode::Enter, KeyCode::Space])
+}
+
+fn button_node(label: &str) -> AccessibilityNode {
+    let mut node = NodeBuilder::new(Role::Button);
+    node.set_name(label);
+    AccessibilityNode(node)
+}
+
+#[derive(Component)]
+struct MemorizeLabel;
+
+impl MouseHoverTracker {
+    fn from_rect(w: f32, h: f32) -> Self {
+        Self::from_shape(HoverShape::Rectangle(Vec2::new(w/2., h/2.)))
+    }
+
+    pub fn from_shape(shape: HoverShape) -> Self {
+        Self {
+            shape,
+            is_hovered: false,
+            priority: 0,
+            pass_through: false,
+            is_pressed: false,
+        }
+    }
+
+    /// Pressed and still under the cursor. Dragging off cancels the click,
+    /// until the cursor comes back.
+    fn is_held(&self) -> bool {
+        self.is_pressed && self.is_hovered
+    }
+
+    fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    fn pass_through(mut self) -> Self {
+        self.pass_through = true;
+        self
+    }
+
+    /// Returns true if the hover state changed
+    fn set_hovered(&mut self, is_hovered: bool) -> bool {
+        let changed = self.is_hovered != is_hovered;
+        self.is_hovered = is_hovered;
+        changed
+    }
+}
+
+fn check_collision_point_tri(p: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+    // Get the barycentric coordinates and check that they're within the triangle
+    // TODO: Maybe this could be faster if the coordinates weren't normalized
+    // to the size of the triangle?
+    let inv_triangle_area = ((b.y - c.y) * (a.x - c.x) + (c.x - b.x) * (a.y - c.y)).recip();
+
+    let bary_a_area = (b.y - c.y) * (p.x - c.x) + (c.x - b.x) * (p.y - c.y);
+    let bary_b_area = (c.y - a.y) * (p.x - c.x) + (a.x - c.x) * (p.y - c.y);
+
+    let bary_a = bary_a_area * inv_triangle_area;
+    let bary_b = bary_b_area * inv_triangle_area;
+    let bary_c = 1. - bary_a - bary_b;
+
+    (bary_a > 0.) && (bary_b > 0.) && (bary_c > 0.)
+}
+
+fn check_collision_point_circle(p: Vec2, radius: f32) -> bool {
+    p.length_squared() <= radius * radius
+}
+
+fn check_collision_point_polygon(p: Vec2, points: &[Vec2]) -> bool {
+    // Count how many edges a ray going right from the point crosses, which
+    // is odd when it's inside, whether the polygon is convex or not
+    let mut inside = false;
+    let mut prev = match points.last() {
+        Some(last) => *last,
+        None => return false,
+    };
+    for &point in points {
+        if (point.y > p.y) != (prev.y > p.y) {
+            let crossing_x = point.x + (p.y - point.y) * (prev.x - point.x) / (prev.y - point.y);
+            if p.x < crossing_x {
+                inside = !inside;
+            }
+        }
+        prev = point;
+    }
+    inside
+}
+
+fn check_collision_point_rounded_rect(p: Vec2, half_size: Vec2, corner_radius: f32) -> bool {
+    // Shrink the rectangle by the corner radius, then it's inside if it's
+    // within the radius of that smaller rectangle
+    let corner_radius = corner_radius.min(half_size.min_element());
+    let inner = half_size - Vec2::splat(corner_radius);
+    let outside_inner = (p.abs() - inner).max(Vec2::ZERO);
+    outside_inner.length_squared() <= corner_radius * corner_radius
+}
+
+fn check_collision_point_annular_sector(
+    p: Vec2,
+    inner_radius: f32,
+    outer_radius: f32,
+    start_angle: f32,
+    end_angle: f32,
+) -> bool {
+    let distance_squared = p.length_squared();
+    if distance_squared < inner_radius * inner_radius || distance_squared > outer_radius * outer_radius {
+        return false;
+    }
+
+    let sweep = end_angle - start_angle;
+    if sweep >= std::f32::consts::TAU {
+        return true;
+    }
+    let angle = (p.y.atan2(p.x) - start_angle).rem_euclid(std::f32::consts::TAU);
+    angle <= sweep
+}
+
+fn setup(
+    world: &mut World,
+) {
+    world.spawn((Camera2dBundle::default(), MainCamera));
+
+    let mut announcer = NodeBuilder::new(Role::Status);
+    announcer.set_live(Live::Polite);
+    world.spawn((AccessibilityNode(announcer), Announcer));
+
+    let mut system_map = HashMap::new();
+    for scene in Scene::iter() {
+        if let Some(system_id) = match scene {
+            Scene::Startup => None,
+            Scene::ClickToStart => Some(world.register_system(setup_click_to_start_scene)),
+            Scene::MainMenu => Some(world.register_system(setup_main_menu)),
+            Scene::Credits => Some(world.register_system(setup_credits)),
+            Scene::Game => Some(world.register_system(setup_game)),
+            Scene::Score => Some(world.register_system(setup_score)),
+            Scene::ModeSelect => Some(world.register_system(setup_mode_select)),
+            Scene::PlayerSelect => Some(world.register_system(setup_player_select)),
+            Scene::Settings => Some(world.register_system(setup_settings)),
+            Scene::Stats => Some(world.register_system(setup_stats)),
+        } {
+            system_map.insert(scene, system_id);
+        }
+    }
+
+    let setup_systems = SceneSetupSystem { system_map };
+    world.insert_resource(setup_systems);
+
+    let button_systems = ButtonSystems {
+        reset_settings: world.register_system(reset_settings),
+        resume_game: world.register_system(resume_game),
+        continue_game: world.register_system(continue_game),
+    };
+    world.insert_resource(button_systems);
+}
+
+fn load_assets(
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    commands.insert_resource(
+        PatternSounds(
+            asset_server.load("sounds/drop_003_p0.ogg"),
+            asset_server.load("sounds/drop_003_p1.ogg"),
+            asset_server.load("sounds/drop_003_p2.ogg"),
+            asset_server.load("sounds/drop_003_p3.ogg"),
+        )
+    );
+}
+
+/// Above every other tracker so clicking anywhere counts, but passing the
+/// cursor through so whatever is under it still gets hovered. Bevy UI buttons
+/// aren't trackers, so clicks on them aren't seen here.
+fn click_anywhere_tracker() -> MouseHoverTracker {
+    MouseHoverTracker::from_rect(99999., 99999.)
+        .with_priority(i32::MAX)
+        .pass_through()
+}
+
+fn setup_click_to_start_scene(
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let text_style = TextStyle {
+        font: font.clone(),
+        font_size: 60.0,
+        color: Color::BLACK,
+    };
+    let text_justification = JustifyText::Center;
+
+    commands.spawn((
+        SpatialBundle::default(),
+        click_anywhere_tracker(),
+        ButtonAction::ChangeScene(Scene::MainMenu),
+        button_node("Begin"),
+        SceneObject(()),
+    )).with_children(|button| {
+        button.spawn(Text2dBundle {
+            text: Text::from_section("Click anywhere to begin", text_style.clone())
+                .with_justify(text_justification),
+            transform: Transform::from_xyz(0.0, 0.0, 1.0),
+            ..default()
+        });
+    });
+}
+
+fn setup_main_menu(
+    window: Query<&Window, With<PrimaryWindow>>,
+    high_scores: Res<HighScores>,
+    best_run: Res<BestRun>,
+    resumable: Res<ResumableGame>,
+    button_systems: Res<ButtonSystems>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let window = window.single();
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+
+    commands.spawn((menu_root(JustifyContent::Center), SceneObject(()))).with_children(|menu| {
+        if let Some(saved) = &resumable.saved {
+            add_button(
+                menu,
+                &font,
+                &format!("Continue (Round {})", saved.max_idx + 1),
+                Color::rgb_u8(0, 228, 48),
+                Color::rgb_u8(0, 117, 44),
+                ButtonAction::RunSystem(button_systems.continue_game),
+            );
+        }
+
+        add_button(
+            menu,
+            &font,
+            "Start Game",
+            Color::rgb_u8(0, 228, 48),
+            Color::rgb_u8(0, 117, 44),
+            ButtonAction::StartMode(GameMode::Classic),
+        );
+
+        // There's nothing to race until a run has been saved
+        let mut ghost_button = add_button(
+            menu,
+            &font,
+            "Race Ghost",
+            Color::rgb_u8(200, 200, 200),
+            Color::rgb_u8(130, 130, 130),
+            ButtonAction::StartMode(GameMode::Ghost),
+        );
+        if best_run.0.is_none() {
+            ghost_button.insert(DisabledButton);
+        }
+
+        add_button(
+            menu,
+            &font,
+            "Game Modes",
+            Color::rgb_u8(253, 249, 0),
+            Color::rgb_u8(200, 190, 0),
+            ButtonAction::ChangeScene(Scene::ModeSelect),
+        );
+
+        // Side by side to leave room for the Continue button
+        menu.spawn(NodeBundle {
+            style: Style {
+                column_gap: Val::Px(MENU_GAP),
+                ..default()
+            },
+            ..default()
+        }).with_children(|row| {
+            add_button(
+                row,
+                &font,
+                "Settings",
+                Color::rgb_u8(200, 200, 200),
+                Color::rgb_u8(130, 130, 130),
+                ButtonAction::ChangeScene(Scene::Settings),
+            );
+
+            add_button(
+                row,
+                &font,
+                "Statistics",
+                Color::rgb_u8(253, 249, 0),
+                Color::rgb_u8(200, 190, 0),
+                ButtonAction::ChangeScene(Scene::Stats),
+            );
+
+            add_button(
+                row,
+                &font,
+                "Credits",
+                Color::rgb_u8(0, 121, 241),
+                Color::rgb_u8(0, 82, 172),
+                ButtonAction::ChangeScene(Scene::Credits),
+            );
+        });
+
+        // Browsers don't let a page close its own tab
+        #[cfg(not(target_arch = "wasm32"))]
+        add_button(
+            menu,
+            &font,
+            "Quit",
+            Color::rgb_u8(200, 200, 200),
+            Color::rgb_u8(130, 130, 130),
+            ButtonAction::Quit,
+        );
+    });
+
+    let text_style = TextStyle {
+        font: font.clone(),
+        font_size: 80.0,
+        color: Color::BLACK,
+    };
+
+    let bl = Vec2::new(-window.resolution.width()/2., -window.resolution.height()/2.);
+
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(format!("High Score: {}", high_scores.0[&GameMode::Classic]), text_style.clone()),
+            text_anchor: Anchor::BottomLeft,
+            transform: Transform::from_xyz(bl.x+10., bl.y, 0.0),
+            ..default()
+        },
+        SceneObject(()),
+    ));
+}
+
+fn setup_mode_select(
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+
+    // Each entry either starts a game straight away or has its own setup
+    // scene first
+    let entries = [
+        ("Pass & Play", ButtonAction::ChangeScene(Scene::PlayerSelect)),
+        ("Add One", ButtonAction::StartMode(GameMode::AddOne)),
+        ("Blind Add One", ButtonAction::StartMode(GameMode::AddOneStrict)),
+        ("Reverse", ButtonAction::StartMode(GameMode::Reverse)),
+        ("Mirror", ButtonAction::StartMode(GameMode::Mirror)),
+        ("Sprint", ButtonAction::StartMode(GameMode::Sprint)),
+        ("Rapid", ButtonAction::StartMode(GameMode::Rapid)),
+    ];
+
+    commands.spawn((menu_root(JustifyContent::Center), SceneObject(()))).with_children(|menu| {
+        menu.spawn(menu_title("Game Modes", &font));
+
+        menu.spawn(NodeBundle {
+            style: Style {
+                display: Display::Grid,
+                grid_template_columns: RepeatedGridTrack::flex(2, 1.0),
+                column_gap: Val::Px(MENU_GAP),
+                row_gap: Val::Px(MENU_GAP),
+                ..default()
+            },
+            ..default()
+        }).with_children(|grid| {
+            for (text, action) in entries {
+                add_button(
+                    grid,
+                    &font,
+                    text,
+                    Color::rgb_u8(253, 249, 0),
+                    Color::rgb_u8(200, 190, 0),
+                    action,
+                );
+            }
+        });
+
+        add_button(
+            menu,
+            &font,
+            "Back",
+            Color::rgb_u8(200, 200, 200),
+            Color::rgb_u8(130, 130, 130),
+            ButtonAction::ChangeScene(Scene::MainMenu),
+        );
+    });
+}
+
+fn setup_player_select(
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+
+    commands.spawn((menu_root(JustifyContent::Center), SceneObject(()))).with_children(|menu| {
+        menu.spawn(menu_title("How many players?", &font));
+
+        for players in 2..=4 {
+            add_button(
+                menu,
+                &font,
+                &format!("{players} Players"),
+                Color::rgb_u8(253, 249, 0),
+                Color::rgb_u8(200, 190, 0),
+                ButtonAction::StartMode(GameMode::HotSeat(players)),
+            );
+        }
+
+        add_button(
+            menu,
+            &font,
+            "Back",
+            Color::rgb_u8(200, 200, 200),
+            Color::rgb_u8(130, 130, 130),
+            ButtonAction::ChangeScene(Scene::ModeSelect),
+        );
+    });
+}
+
+fn setup_settings(
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+    button_systems: Res<ButtonSystems>,
+    mut commands: Commands,
+) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+
+    let rows = [
+        Setting::Palette,
+        Setting::Glyphs,
+        Setting::Accessibility,
+        Setting::ReducedFlash,
+        Setting::FlashDuty,
+        Setting::Layout,
+    ];
+
+    commands.spawn((menu_root(JustifyContent::Center), SceneObject(()))).with_children(|menu| {
+        menu.spawn(menu_title("Settings", &font));
+
+        menu.spawn(NodeBundle {
+            style: Style {
+                display: Display::Grid,
+                grid_template_columns: RepeatedGridTrack::flex(2, 1.0),
+                column_gap: Val::Px(MENU_GAP),
+                row_gap: Val::Px(MENU_GAP),
+                ..default()
+            },
+            ..default()
+        }).with_children(|grid| {
+            for setting in rows {
+                add_button(
+                    grid,
+                    &font,
+                    &setting.label(&settings),
+                    Color::rgb_u8(200, 200, 200),
+                    Color::rgb_u8(130, 130, 130),
+                    ButtonAction::ToggleSetting(setting),
+                );
+            }
+        });
+
+        add_button(
+            menu,
+            &font,
+            "Reset to Defaults",
+            Color::rgb_u8(200, 200, 200),
+            Color::rgb_u8(130, 130, 130),
+            ButtonAction::RunSystem(button_systems.reset_settings),
+        );
+
+        add_button(
+            menu,
+            &font,
+            "Back",
+            Color::rgb_u8(200, 200, 200),
+            Color::rgb_u8(130, 130, 130),
+            ButtonAction::ChangeScene(Scene::MainMenu),
+        );
+    });
+}
+
+const STATS_FONT_SIZE: f32 = 32.0;
+
+const HISTOGRAM_BUCKETS: u32 = 10;
+const HISTOGRAM_HEIGHT: f32 = 120.0;
+const HISTOGRAM_BAR_WIDTH: f32 = 40.0;
+
+fn format_duration(seconds: u32) -> String {
+    let (hours, minutes) = (seconds / 3600, seconds / 60 % 60);
+    if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else {
+        format!("{minutes}m {}s", seconds % 60)
+    }
+}
+
+fn setup_stats(
+    asset_server: Res<AssetServer>,
+    stats: Res<LifetimeStats>,
+    settings: Res<Settings>,
+    mut commands: Commands,
+) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let text_style = TextStyle {
+        font: font.clone(),
+        font_size: STATS_FONT_SIZE,
+        color: Color::BLACK,
+    };
+
+    let mut lines = vec![format!("Games played: {}", stats.games_played())];
+    if let (Some(average), Some(median)) = (stats.average_score(), stats.median_score()) {
+        lines.push(format!("Average score: {average:.1}   Median score: {median}"));
+    }
+    lines.push(format!("Pads pressed: {}", stats.pads_pressed));
+    if let Some(accuracy) = stats.accuracy() {
+        lines.push(format!("Accuracy: {:.1}%", accuracy * 100.0));
+    }
+    lines.push(format!("Longest session: {}", format_duration(stats.longest_session_seconds)));
+
+    commands.spawn((menu_root(JustifyContent::Center), SceneObject(()))).with_children(|menu| {
+        menu.spawn(menu_title("Statistics", &font));
+
+        for line in lines {
+            menu.spawn(TextBundle::from_section(line, text_style.clone()));
+        }
+
+        // The pad's name along with a swatch of its colour in the current
+        // palette
+        if let Some(pad) = stats.most_missed_pad() {
+            menu.spawn(NodeBundle {
+                style: Style {
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(MENU_GAP / 2.0),
+                    ..default()
+                },
+                ..default()
+            }).with_children(|row| {
+                row.spawn(TextBundle::from_section(
+                    format!("Most missed pad: {}", PAD_NAMES[pad as usize]),
+                    text_style.clone(),
+                ));
+                row.spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(STATS_FONT_SIZE),
+                        height: Val::Px(STATS_FONT_SIZE),
+                        ..default()
+                    },
+                    background_color: settings.palette.pad_colors(pad).1.into(),
+                    ..default()
+                });
+            });
+        }
+
+        if stats.games_played() > 0 {
+            spawn_score_histogram(menu, &text_style, &stats);
+        }
+
+        add_button(
+            menu,
+            &font,
+            "Back",
+            Color::rgb_u8(200, 200, 200),
+            Color::rgb_u8(130, 130, 130),
+            ButtonAction::ChangeScene(Scene::MainMenu),
+        );
+    });
+}
+
+/// Final scores grouped into equal ranges, one bar per range
+fn spawn_score_histogram(parent: &mut ChildBuilder, text_style: &TextStyle, stats: &LifetimeStats) {
+    let (bucket_size, buckets) = stats.score_buckets();
+    let tallest = buckets.iter().copied().max().unwrap_or(1).max(1);
+
+    let label_style = TextStyle {
+        font_size: STATS_FONT_SIZE * 0.6,
+        ..text_style.clone()
+    };
+
+    parent.spawn(NodeBundle {
+        style: Style {
+            align_items: AlignItems::FlexEnd,
+            column_gap: Val::Px(MENU_GAP / 2.0),
+            margin: UiRect::vertical(Val::Px(MENU_GAP)),
+            ..default()
+        },
+        ..default()
+    }).with_children(|histogram| {
+        for (idx, &count) in buckets.iter().enumerate() {
+            let low = idx as u32 * bucket_size;
+            let label = if bucket_size == 1 {
+                low.to_string()
+            } else {
+                format!("{}-{}", low, low + bucket_size - 1)
+            };
+
+            histogram.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            }).with_children(|column| {
+                column.spawn(TextBundle::from_section(count.to_string(), label_style.clone()));
+                column.spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(HISTOGRAM_BAR_WIDTH),
+                        height: Val::Px(HISTOGRAM_HEIGHT * count as f32 / tallest as f32),
+                        ..default()
+                    },
+                    background_color: Color::rgb_u8(0, 121, 241).into(),
+                    ..default()
+                });
+                column.spawn(TextBundle::from_section(label, label_style.clone()));
+            });
+        }
+    });
+}
+
+fn setup_credits(
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let text_style = TextStyle {
+        font: font.clone(),
+        font_size: 80.0,
+        color: Color::BLACK,
+    };
+    let text_justification = JustifyText::Center;
+
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section("Game by Stephen Molyneaux 2024", text_style.clone())
+                .with_justify(text_justification),
+            transform: Transform::from_xyz(0.0, 0.0, 0.0),
+            ..default()
+        },
+        click_anywhere_tracker(),
+        ButtonAction::ChangeScene(Scene::MainMenu),
+        button_node("Return to menu"),
+        SceneObject(()),
+    ));
+
+    let text_style = TextStyle {
+        font: font.clone(),
+        font_size: 40.0,
+        color: Color::BLACK,
+    };
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section("Created with Bevy", text_style.clone())
+                .with_justify(text_justification),
+            transform: Transform::from_xyz(0.0, -80.0, 0.0),
+            ..default()
+        },
+        SceneObject(()),
+    ));
+
+    let text_style = TextStyle {
+        font: font.clone(),
+        font_size: 60.0,
+        color: Color::BLACK,
+    };
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section("Click to Return", text_style.clone())
+                .with_justify(text_justification),
+            transform: Transform::from_xyz(0.0, -220.0, 0.0),
+            ..default()
+        },
+        SceneObject(()),
+    ));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn setup_game(
+    asset_server: Res<AssetServer>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    mode: Res<SelectedMode>,
+    best_run: Res<BestRun>,
+    high_scores: Res<HighScores>,
+    sprint_record: Res<SprintRecord>,
+    settings: Res<Settings>,
+    mut timer: ResMut<PatternAnimationTimer>,
+    mut commands: Commands,
+    mut state: ResMut<GameState>,
+    mut game_over: ResMut<PendingGameOver>,
+    mut resumable: ResMut<ResumableGame>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let window = window.single();
+
+    game_over.0 = None;
+
+    timer.0.set_duration(Duration::from_secs_f32(PATTERN_STEP_SECONDS));
+    timer.0.reset();
+    state.reset();
+    let resume_next = std::mem::take(&mut resumable.resume_next);
+    let saved = resumable.saved.as_ref().filter(|saved| resume_next && saved.mode == mode.0);
+    state.seed = saved.map_or_else(rand::random, |saved| saved.seed);
+    match (mode.0, &best_run.0) {
+        // The ghost only makes sense if we play the same pattern it did
+        (GameMode::Ghost, Some(run)) => state.pattern = run.pattern.clone(),
+        // The players provide the pattern themselves
+        (GameMode::AddOne | GameMode::AddOneStrict, _) => {
+            state.add_one = true;
+            state.skip_playback = mode.0 == GameMode::AddOneStrict;
+            // Each added pad is a round, so the game ends on a full pattern
+            // rather than growing past it
+            state.length_limit = Some(MAX_PATTERN_LEN);
+        }
+        _ => {
+            let length = if mode.0 == GameMode::Sprint { SPRINT_LENGTH } else { MAX_PATTERN_LEN };
+            let mut rng = StdRng::seed_from_u64(state.seed);
+            state.pattern = (0..length).map(|_| rng.gen_range(0..4)).collect();
+        }
+    }
+    state.input_transform = mode.0.input_transform();
+    state.speed_up = mode.0 == GameMode::Rapid;
+    state.max_idx = 0;
+    // A sprint is a single round through the whole pattern, so it starts on
+    // the last round and is won by completing it
+    if mode.0 == GameMode::Sprint {
+        state.max_idx = SPRINT_LENGTH - 1;
+        state.length_limit = Some(SPRINT_LENGTH);
+    }
+    if let GameMode::HotSeat(players) = mode.0 {
+        state.players = vec![HotSeatPlayer::default(); players as usize];
+    }
+    if let Some(saved) = saved {
+        println!("Resuming {:?} game at round {}", saved.mode, saved.max_idx + 1);
+        // The seed regenerates the rest of a random pattern, and the saved
+        // part is all there is of a pattern the player built
+        let played = saved.pattern.len().min(state.pattern.len());
+        if state.add_one {
+            state.pattern = saved.pattern.clone();
+        } else {
+            state.pattern[..played].copy_from_slice(&saved.pattern[..played]);
+        }
+        state.max_idx = saved.max_idx;
+        state.resumed = true;
+        if state.speed_up {
+            timer.0.set_duration(Duration::from_secs_f32(rapid_step_seconds(state.max_idx, settings.reduced_flash)));
+        }
+    }
+
+    // Create 4 touch areas
+    let pad_colors: [(Color, Color); 4] = std::array::from_fn(|pad| {
+        let (base, lit) = settings.palette.pad_colors(pad as u8);
+        if settings.reduced_flash {
+            (base, cap_lightness_change(base, lit, REDUCED_FLASH_MAX_LIGHTNESS_CHANGE))
+        } else {
+            (base, lit)
+        }
+    });
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+
+    let pads = match settings.layout {
+        BoardLayout::Triangles => triangle_board(window),
+        BoardLayout::Round => {
+            let radius = window.resolution.width().min(window.resolution.height()) / 2. * ROUND_BOARD_SCALE;
+            spawn_round_board_body(&mut commands, &mut meshes, &mut materials, &font, radius);
+            round_board(radius)
+        }
+    };
+
+    for (pad, geometry) in pads.into_iter().enumerate() {
+        let (color, hover_color) = pad_colors[pad];
+        let pressed_color = if settings.reduced_flash {
+            cap_lightness_change(color, hover_color * PRESSED_DARKEN, REDUCED_FLASH_MAX_LIGHTNESS_CHANGE)
+        } else {
+            hover_color * PRESSED_DARKEN
+        };
+        let center = geometry.shape.center();
+
+        commands.spawn((
+            MaterialMesh2dBundle {
+                mesh: Mesh2dHandle(meshes.add(geometry.mesh)),
+                material: materials.add(color),
+                transform: Transform::from_xyz(0., 0., 0.),
+                ..default()
+            },
+            MouseHoverDisable,
+            MouseHoverTracker::from_shape(geometry.shape),
+            MouseOverMaterial(materials.add(hover_color)),
+            MouseOutMaterial(materials.add(color)),
+            MousePressedMaterial(materials.add(pressed_color)),
+            PatternIdx(pad as u8),
+            SceneObject(()),
+        )).with_children(|pad_entity| {
+            // Parented to the pad so it moves along with the press and shake
+            // animations
+            spawn_pad_glyph(
+                pad_entity,
+                &mut meshes,
+                &mut materials,
+                &font,
+                settings.glyphs,
+                pad as u8,
+                center,
+            );
+        });
+
+        // Outlines stand in for flashing the whole pad with reduced flashing
+        commands.spawn((
+            MaterialMesh2dBundle {
+                mesh: Mesh2dHandle(meshes.add(geometry.border)),
+                material: materials.add(PAD_BORDER_COLOR),
+                transform: Transform::from_xyz(0., 0., 0.25),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            PadBorder(pad as u8),
+            SceneObject(()),
+        ));
+    }
+
+    let text_style = TextStyle {
+        font: font.clone(),
+        font_size: 80.0,
+        color: Color::BLACK,
+    };
+    let text_justification = JustifyText::Center;
+
+    // The round board's hub shows the state instead
+    if settings.layout == BoardLayout::Triangles {
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(state.memorize_text(), text_style.clone())
+                    .with_justify(text_justification),
+                transform: Transform::from_xyz(0.0, 0.0, 1.0),
+                ..default()
+            },
+            Tween::fade_in(LABEL_FADE_SECONDS),
+            MemorizeLabel,
+            SceneObject(()),
+        ));
+    }
+
+    // The ghost is racing the best classic run, so that's the score to beat
+    let record_mode = if mode.0 == GameMode::Ghost { GameMode::Classic } else { mode.0 };
+    let to_beat = if let Some(high_score) = high_scores.0.get(&record_mode) {
+        Some(format!("High score: {}", high_score))
+    } else if mode.0 == GameMode::Sprint {
+        sprint_record.0.map(|record| format!("Best time: {}", format_millis(record)))
+    } else {
+        None
+    };
+    spawn_round_hud(&mut commands, &font, to_beat);
+}
+
+const HUD_FONT_SIZE: f32 = 32.0;
+
+const HUD_DOT_SIZE: f32 = 12.0;
+
+const HUD_DOT_EMPTY_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.25);
+
+const HUD_DOT_WATCH_COLOR: Color = Color::rgb(0.35, 0.35, 0.35);
+
+const HUD_DOT_INPUT_COLOR: Color = Color::WHITE;
+
+#[derive(Component)]
+struct HudRoundText;
+
+#[derive(Component)]
+struct HudTurnText;
+
+/// Holds one dot per pad in the current round
+#[derive(Component)]
+struct HudProgressDots;
+
+#[derive(Component)]
+struct HudDot(usize);
+
+/// Spawns the in-game bar along the top of the window, filled in by
+/// `round_hud_system`
+fn spawn_round_hud(commands: &mut Commands, font: &Handle<Font>, to_beat: Option<String>) {
+    let text_style = TextStyle {
+        font: font.clone(),
+        font_size: HUD_FONT_SIZE,
+        color: Color::BLACK,
+    };
+    let column = Style {
+        flex_direction: FlexDirection::Column,
+        row_gap: Val::Px(4.0),
+        ..default()
+    };
+
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(0.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::SpaceBetween,
+                align_items: AlignItems::FlexStart,
+                padding: UiRect::all(Val::Px(16.0)),
+                ..default()
+            },
+            ..default()
+        },
+        SceneObject(()),
+    )).with_children(|hud| {
+        hud.spawn(NodeBundle { style: column.clone(), ..default() }).with_children(|left| {
+            left.spawn((TextBundle::from_section("", text_style.clone()), HudRoundText));
+            if let Some(to_beat) = to_beat {
+                left.spawn(TextBundle::from_section(to_beat, text_style.clone()));
+            }
+        });
+
+        hud.spawn((
+            NodeBundle {
+                style: Style {
+                    flex_wrap: FlexWrap::Wrap,
+                    justify_content: JustifyContent::Center,
+                    max_width: Val::Percent(50.0),
+                    column_gap: Val::Px(6.0),
+                    row_gap: Val::Px(6.0),
+                    ..default()
+                },
+                ..default()
+            },
+            HudProgressDots,
+        ));
+
+        hud.spawn((TextBundle::from_section("", text_style.clone()), HudTurnText));
+    });
+}
+
+fn round_hud_system(
+    mut commands: Commands,
+    state: Res<GameState>,
+    mode: Res<SelectedMode>,
+    mut round_text: Query<&mut Text, (With<HudRoundText>, Without<HudTurnText>)>,
+    mut turn_text: Query<&mut Text, (With<HudTurnText>, Without<HudRoundText>)>,
+    containers: Query<(Entity, Option<&Children>), With<HudProgressDots>>,
+    mut dots: Query<(&HudDot, &mut BackgroundColor)>,
+) {
+    // A sprint only has the one round
+    let round = if mode.0 == GameMode::Sprint {
+        "Sprint".to_string()
+    } else {
+        format!("Round {}", state.max_idx as u32 + 1)
+    };
+    for mut text in &mut round_text {
+        text.sections[0].value.clone_from(&round);
+    }
+
+    let turn = if !state.interactive {
+        "Watch"
+    } else if state.awaiting_extension() {
+        ADD_ONE_TEXT
+    } else {
+        "Your turn"
+    };
+    for mut text in &mut turn_text {
+        text.sections[0].value = if state.players.is_empty() {
+            turn.to_string()
+        } else {
+            format!("Player {}: {}", state.current_player + 1, turn)
+        };
+    }
+
+    // Rebuild the dots whenever the round changes length
+    let round_len = state.round_len();
+    for (container, children) in &containers {
+        if children.map_or(0, |children| children.len()) != round_len {
+            commands.entity(container).despawn_descendants().with_children(|parent| {
+                for dot in 0..round_len {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Px(HUD_DOT_SIZE),
+                                height: Val::Px(HUD_DOT_SIZE),
+                                ..default()
+                            },
+                            background_color: HUD_DOT_EMPTY_COLOR.into(),
+                            ..default()
+                        },
+                        HudDot(dot),
+                    ));
+                }
+            });
+        }
+    }
+
+    let filled = if state.interactive { HUD_DOT_INPUT_COLOR } else { HUD_DOT_WATCH_COLOR };
+    for (dot, mut color) in &mut dots {
+        *color = if dot.0 < state.idx as usize { filled } else { HUD_DOT_EMPTY_COLOR }.into();
+    }
+}
+
+const GLYPH_COLOR: Color = Color::rgb(0.2, 0.2, 0.2);
+
+const PAD_BORDER_COLOR: Color = Color::rgb(0.2, 0.2, 0.2);
+
+const PAD_BORDER_WIDTH: f32 = 16.0;
+
+/// Everything needed to spawn one pad
+struct PadGeometry {
+    mesh: Mesh,
+    shape: HoverShape,
+    /// Outline shown during playback with reduced flashing
+    border: Mesh,
+}
+
+/// The original board, with the window split into four triangles
+fn triangle_board(window: &Window) -> [PadGeometry; 4] {
+    let center = Vec2::new(0., 0.);
+    let tl = Vec2::new(-window.resolution.width()/2., window.resolution.height()/2.);
+    let tr = Vec2::new(window.resolution.width()/2., window.resolution.height()/2.);
+    let bl = Vec2::new(-window.resolution.width()/2., -window.resolution.height()/2.);
+    let br = Vec2::new(window.resolution.width()/2., -window.resolution.height()/2.);
+
+    [
+        (center, tl, tr),
+        (center, tr, br),
+        (center, bl, br),
+        (center, tl, bl),
+    ].map(|(a, b, c)| {
+        let mesh = Mesh::from(Triangle2d::new(a, b, c));
+        PadGeometry {
+            shape: HoverShape::from_mesh(&mesh).expect("pads are triangle meshes"),
+            mesh,
+            border: triangle_border_mesh(a, b, c, PAD_BORDER_WIDTH),
+        }
+    })
+}
+
+/// Board radius as a fraction of the smaller half of the window, leaving
+/// room for the HUD
+const ROUND_BOARD_SCALE: f32 = 0.82;
+
+/// Pad inner and outer radius as fractions of the board radius
+const ROUND_PAD_INNER: f32 = 0.4;
+
+const ROUND_PAD_OUTER: f32 = 0.93;
+
+const ROUND_HUB_RADIUS: f32 = 0.34;
+
+/// Angle left empty between neighbouring pads
+const ROUND_PAD_GAP: f32 = 0.08;
+
+const ROUND_RIM_COLOR: Color = Color::rgb(0.12, 0.12, 0.12);
+
+const ROUND_HUB_COLOR: Color = Color::rgb(0.2, 0.2, 0.2);
+
+/// Segments used for a quarter of a circle
+const ROUND_SEGMENTS: usize = 32;
+
+/// Quarter rings around a hub. Pads keep the same order as the triangles:
+/// top, right, bottom, left.
+fn round_board(radius: f32) -> [PadGeometry; 4] {
+    let inner_radius = radius * ROUND_PAD_INNER;
+    let outer_radius = radius * ROUND_PAD_OUTER;
+    let quarter = std::f32::consts::FRAC_PI_2;
+
+    [1., 0., 3., 2.].map(|direction: f32| {
+        let start_angle = direction * quarter - quarter / 2. + ROUND_PAD_GAP / 2.;
+        let end_angle = start_angle + quarter - ROUND_PAD_GAP;
+        PadGeometry {
+            mesh: annular_sector_mesh(inner_radius, outer_radius, start_angle, end_angle),
+            shape: HoverShape::AnnularSector { inner_radius, outer_radius, start_angle, end_angle },
+            border: annular_sector_border_mesh(inner_radius, outer_radius, start_angle, end_angle, PAD_BORDER_WIDTH),
+        }
+    })
+}
+
+/// The rim behind the pads and the hub in the middle, which shows the round
+/// and whose turn it is
+fn spawn_round_board_body(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    font: &Handle<Font>,
+    radius: f32,
+) {
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(Circle::new(radius))),
+            material: materials.add(ROUND_RIM_COLOR),
+            transform: Transform::from_xyz(0., 0., -0.5),
+            ..default()
+        },
+        SceneObject(()),
+    ));
+
+    let hub_radius = radius * ROUND_HUB_RADIUS;
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(Circle::new(hub_radius))),
+            material: materials.add(ROUND_HUB_COLOR),
+            transform: Transform::from_xyz(0., 0., 0.1),
+            ..default()
+        },
+        SceneObject(()),
+    ));
+
+    let round_style = TextStyle {
+        font: font.clone(),
+        font_size: 40.0,
+        color: Color::WHITE,
+    };
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section("", round_style).with_justify(JustifyText::Center),
+            transform: Transform::from_xyz(0., hub_radius * 0.2, 1.),
+            ..default()
+        },
+        HudRoundText,
+        SceneObject(()),
+    ));
+
+    let turn_style = TextStyle {
+        font: font.clone(),
+        font_size: 22.0,
+        color: Color::WHITE,
+    };
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section("", turn_style).with_justify(JustifyText::Center),
+            transform: Transform::from_xyz(0., -hub_radius * 0.3, 1.),
+            ..default()
+        },
+        HudTurnText,
+        SceneObject(()),
+    ));
+}
+
+/// Points along an arc, counterclockwise from `start_angle` to `end_angle`
+fn arc_points(radius: f32, start_angle: f32, end_angle: f32) -> Vec<Vec2> {
+    (0..=ROUND_SEGMENTS)
+        .map(|i| {
+            let angle = start_angle + (end_angle - start_angle) * i as f32 / ROUND_SEGMENTS as f32;
+            Vec2::from_angle(angle) * radius
+        })
+        .collect()
+}
+
+fn annular_sector_mesh(inner_radius: f32, outer_radius: f32, start_angle: f32, end_angle: f32) -> Mesh {
+    let outer = arc_points(outer_radius, start_angle, end_angle);
+    let inner = arc_points(inner_radius, start_angle, end_angle);
+    let positions: Vec<[f32; 3]> = outer
+        .iter()
+        .chain(inner.iter())
+        .map(|p| [p.x, p.y, 0.0])
+        .collect();
+
+    // Two triangles for each segment, joining the outer and inner arcs
+    let n = ROUND_SEGMENTS as u32 + 1;
+    let mut indices = Vec::new();
+    for i in 0..ROUND_SEGMENTS as u32 {
+        indices.extend_from_slice(&[i, n + i, i + 1, i + 1, n + i, n + i + 1]);
+    }
+
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_indices(Indices::U32(indices))
+}
+
+fn annular_sector_border_mesh(
+    inner_radius: f32,
+    outer_radius: f32,
+    start_angle: f32,
+    end_angle: f32,
+    width: f32,
+) -> Mesh {
+    // The outline of the sector, and the same outline moved inwards by the
+    // border width. Moving the straight edges by a fixed distance means a
+    // bigger change in angle the closer they are to the middle.
+    let inset = |radius: f32| width / radius;
+    let outline: Vec<Vec2> = arc_points(outer_radius, start_angle, end_angle)
+        .into_iter()
+        .chain(arc_points(inner_radius, end_angle, start_angle))
+        .collect();
+    let inset_outer = outer_radius - width;
+    let inset_inner = inner_radius + width;
+    let inset_outline: Vec<Vec2> = arc_points(inset_outer, start_angle + inset(inset_outer), end_angle - inset(inset_outer))
+        .into_iter()
+        .chain(arc_points(inset_inner, end_angle - inset(inset_inner), start_angle + inset(inset_inner)))
+        .collect();
+
+    let positions: Vec<[f32; 3]> = outline
+        .iter()
+        .chain(inset_outline.iter())
+        .map(|p| [p.x, p.y, 0.0])
+        .collect();
+
+    // Two triangles for each edge, joining the outline and the inset outline
+    let n = outline.len() as u32;
+    let mut indices = Vec::new();
+    for i in 0..n {
+        let next = (i + 1) % n;
+        indices.extend_from_slice(&[i, next, n + i, next, n + next, n + i]);
+    }
+
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_indices(Indices::U32(indices))
+}
+
+/// A band of constant `width` just inside the edges of the triangle
+fn triangle_border_mesh(a: Vec2, b: Vec2, c: Vec2, width: f32) -> Mesh {
+    // Shrinking the triangle towards its incenter moves every edge inwards
+    // by the same distance
+    let (len_a, len_b, len_c) = ((b - c).length(), (c - a).length(), (a - b).length());
+    let perimeter = len_a + len_b + len_c;
+    let incenter = (a * len_a + b * len_b + c * len_c) / perimeter;
+    let area = (b - a).perp_dot(c - a).abs() / 2.0;
+    let inradius = 2.0 * area / perimeter;
+    let scale = ((inradius - width) / inradius).max(0.0);
+
+    let outer = [a, b, c];
+    let inner = outer.map(|p| incenter + (p - incenter) * scale);
+
+    let positions: Vec<[f32; 3]> = outer
+        .iter()
+        .chain(inner.iter())
+        .map(|p| [p.x, p.y, 0.0])
+        .collect();
+
+    // Two triangles for each edge, joining the outer and inner corners
+    let mut indices = Vec::new();
+    for i in 0..3u32 {
+        let next = (i + 1) % 3;
+        indices.extend_from_slice(&[i, next, 3 + i, next, 3 + next, 3 + i]);
+    }
+
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_indices(Indices::U32(indices))
+}
+
+/// Marks a pad with a shape or number so it doesn't rely on colour alone
+fn spawn_pad_glyph(
+    parent: &mut ChildBuilder,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    font: &Handle<Font>,
+    glyphs: PadGlyphs,
+    pad: u8,
+    center: Vec2,
+) {
+    let transform = Transform::from_xyz(center.x, center.y, 0.5);
+    match glyphs {
+        PadGlyphs::Off => {}
+        PadGlyphs::Numbers => {
+            let text_style = TextStyle {
+                font: font.clone(),
+                font_size: 80.0,
+                color: GLYPH_COLOR,
+            };
+            parent.spawn(Text2dBundle {
+                text: Text::from_section((pad + 1).to_string(), text_style)
+                    .with_justify(JustifyText::Center),
+                transform,
+                ..default()
+            });
+        }
+        PadGlyphs::Shapes => {
+            let size = 30.0;
+            let (mesh, transform) = match pad {
+                0 => (meshes.add(Circle::new(size)), transform),
+                1 => (
+                    meshes.add(Triangle2d::new(
+                        Vec2::new(0.0, size),
+                        Vec2::new(-size, -size),
+                        Vec2::new(size, -size),
+                    )),
+                    transform,
+                ),
+                2 => (meshes.add(Rectangle::new(size * 2.0, size * 2.0)), transform),
+                _ => (
+                    meshes.add(Rectangle::new(size * 1.6, size * 1.6)),
+                    transform.with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+                ),
+            };
+            parent.spawn(MaterialMesh2dBundle {
+                mesh: Mesh2dHandle(mesh),
+                material: materials.add(GLYPH_COLOR),
+                transform,
+                ..default()
+            });
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn pattern_playback_system(
+    mut commands: Commands,
+    sounds: Res<PatternSounds>,
+    time: Res<Time>,
+    mut query: Query<(Entity, &PatternIdx, &MouseOverMaterial, &MouseOutMaterial, &mut Handle<ColorMaterial>)>,
+    mut label: Query<(Entity, &mut Text), With<MemorizeLabel>>,
+    mut timer: ResMut<PatternAnimationTimer>,
+    mut state: ResMut<GameState>,
+    mut ghost: ResMut<GhostCursor>,
+    mut borders: Query<(&PadBorder, &mut Visibility)>,
+    settings: Res<Settings>,
+    mut announce: EventWriter<Announce>,
+) {
+    if state.interactive {
+        return;
+    }
+
+    timer.0.tick(time.delta());
+
+    // Turn the pad off for the rest of the step, so the same pad twice in a
+    // row shows up as two flashes
+    if state.playback_lit && timer.0.fraction() >= settings.flash_duty_cycle() {
+        state.playback_lit = false;
+        for (_entity_id, _idx, _over, out, mut mat) in &mut query {
+            *mat = out.0.clone();
+        }
+        for (_border, mut visibility) in &mut borders {
+            *visibility = Visibility::Hidden;
+        }
+    }
+
+    if timer.0.just_finished() {
+        println!("PB system timer just finished");
+        if state.skip_playback || state.idx as usize >= state.round_len() {
+            state.interactive = true;
+            state.idx = 0;
+            state.playback_lit = false;
+            state.round_start = time.elapsed_seconds_f64();
+            state.rounds.push(Vec::new());
+            ghost.0 = 0;
+            for (entity_id, _idx, _over, out, mut mat) in &mut query {
+                *mat = out.0.clone();
+                commands.entity(entity_id).remove::<MouseHoverDisable>();
+            }
+            for (_border, mut visibility) in &mut borders {
+                *visibility = Visibility::Hidden;
+            }
+            for (entity_id, mut text) in &mut label {
+                // With nothing to replay the first press is the new pad
+                if state.awaiting_extension() {
+                    text.sections[0].value = ADD_ONE_TEXT.to_string();
+                } else {
+                    commands.entity(entity_id).insert(Tween::fade_out(LABEL_FADE_SECONDS));
+                }
+            }
+            if state.awaiting_extension() {
+                announce.send(Announce(ADD_ONE_TEXT.to_string()));
+            } else {
+                announce.send(Announce("Your turn".to_string()));
+            }
+        } else {
+            if !state.pattern.is_empty() {
+                println!(
+                    "Playing sound {} for idx {}",
+                    state.pattern[state.idx as usize],
+                    state.idx,
+                );
+                announce.send(Announce(PAD_NAMES[state.pattern[state.idx as usize] as usize].to_string()));
+                commands.spawn(AudioBundle {
+                    source: match state.pattern[state.idx as usize] {
+                        0 => sounds.0.clone(),
+                        1 => sounds.1.clone(),
+                        2 => sounds.2.clone(),
+                        _ => sounds.3.clone(),
+                    },
+                    settings: PlaybackSettings::DESPAWN,
+                });
+            }
+            let lit_pad = state.pattern[state.idx as usize];
+            state.playback_lit = true;
+            if settings.reduced_flash {
+                for (border, mut visibility) in &mut borders {
+                    *visibility = if border.0 == lit_pad {
+                        Visibility::Visible
+                    } else {
+                        Visibility::Hidden
+                    };
+                }
+            } else {
+                for (_entity_id, idx, over, out, mut mat) in &mut query {
+                    if lit_pad == idx.0 {
+                        *mat = over.0.clone();
+                    } else {
+                        *mat = out.0.clone();
+                    }
+                }
+            }
+            state.idx += 1;
+        }
+    }
+}
+
+const ADD_ONE_TEXT: &str = "Add One";
+
+const LABEL_FADE_SECONDS: f32 = 0.3;
+
+/// How long the wrong pad shakes before the score screen
+const GAME_OVER_DELAY_SECONDS: f32 = 0.6;
+
+const PAD_PRESS_SECONDS: f32 = 0.2;
+
+/// How far a pressed pad shrinks at the bottom of the press
+const PAD_PRESS_SCALE: f32 = 0.94;
+
+const PARTICLE_COUNT: usize = 12;
+
+const PARTICLE_SPEED: f32 = 350.0;
+
+const PARTICLE_SECONDS: f32 = 0.5;
+
+const PAD_ERROR_COLOR: Color = Color::rgb(0.85, 0.1, 0.1);
+
+fn pad_feedback_system(
+    mut commands: Commands,
+    mut events: EventReader<PadPressed>,
+    mut pads: Query<(Entity, &PatternIdx, &MouseHoverTracker, &MouseOverMaterial, &MouseOutMaterial, &GlobalTransform, &mut Transform)>,
+    settings: Res<Settings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for press in events.read() {
+        for (entity, idx, tracker, over, out, transform, mut local_transform) in &mut pads {
+            if idx.0 != press.pad {
+                continue;
+            }
+            let center = tracker.shape.center();
+
+            if !press.correct {
+                // A press animation may be cut off part way, so put the pad
+                // back at rest before it shakes
+                local_transform.scale = Vec3::ONE;
+                local_transform.translation = Vec3::new(0.0, 0.0, local_transform.translation.z);
+                let error_color = if settings.reduced_flash {
+                    let base = materials.get(&out.0).map(|material| material.color).unwrap_or(Color::GRAY);
+                    cap_lightness_change(base, PAD_ERROR_COLOR, REDUCED_FLASH_MAX_LIGHTNESS_CHANGE)
+                } else {
+                    PAD_ERROR_COLOR
+                };
+                commands.entity(entity)
+                    .remove::<PadPress>()
+                    .insert(PadShake {
+                        timer: Timer::from_seconds(GAME_OVER_DELAY_SECONDS, TimerMode::Once),
+                        error_material: materials.add(error_color),
+                    });
+                continue;
+            }
+
+            commands.entity(entity).insert(PadPress {
+                timer: Timer::from_seconds(PAD_PRESS_SECONDS, TimerMode::Once),
+                center,
+            });
+
+            // Bursts of particles are exactly the kind of flashing that
+            // reduced flashing is meant to avoid
+            if settings.reduced_flash {
+                continue;
+            }
+
+            let color = materials.get(&over.0).map(|material| material.color).unwrap_or(Color::GRAY);
+            let origin = transform.transform_point(press.position.unwrap_or(center).extend(0.0));
+            let mesh = meshes.add(Circle::new(6.0));
+            for particle in 0..PARTICLE_COUNT {
+                let angle = std::f32::consts::TAU * particle as f32 / PARTICLE_COUNT as f32;
+                let speed = PARTICLE_SPEED * rand::random::<f32>().max(0.4);
+                commands.spawn((
+                    MaterialMesh2dBundle {
+                        mesh: Mesh2dHandle(mesh.clone()),
+                        material: materials.add(color),
+                        transform: Transform::from_xyz(origin.x, origin.y, 0.75),
+                        ..default()
+                    },
+                    Particle {
+                        velocity: Vec2::from_angle(angle) * speed,
+                        lifetime: Timer::from_seconds(PARTICLE_SECONDS, TimerMode::Once),
+                    },
+                    SceneObject(()),
+                ));
+            }
+        }
+    }
+}
+
+/// Pads sit at the origin, so scaling about the pad's center means moving it
+/// towards that center as well
+fn pad_press_animation_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pads: Query<(Entity, &mut PadPress, &mut Transform)>,
+) {
+    for (entity, mut press, mut transform) in &mut pads {
+        press.timer.tick(time.delta());
+        let t = press.timer.fraction();
+        let scale = 1.0 - (1.0 - PAD_PRESS_SCALE) * (std::f32::consts::PI * t).sin();
+        transform.scale = Vec3::new(scale, scale, 1.0);
+        transform.translation = (press.center * (1.0 - scale)).extend(transform.translation.z);
+
+        if press.timer.finished() {
+            transform.scale = Vec3::ONE;
+            transform.translation = Vec3::new(0.0, 0.0, transform.translation.z);
+            commands.entity(entity).remove::<PadPress>();
+        }
+    }
+}
+
+fn pad_shake_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut pads: Query<(Entity, &mut PadShake, &mut Transform, &mut Handle<ColorMaterial>, &MouseOutMaterial)>,
+) {
+    for (entity, mut shake, mut transform, mut material, out) in &mut pads {
+        shake.timer.tick(time.delta());
+
+        // Fade the shake out so the pad settles back in place
+        let t = shake.timer.fraction();
+        let amplitude = if settings.reduced_flash { 0.0 } else { 12.0 * (1.0 - t) };
+        transform.translation.x = amplitude * (t * 40.0).sin();
+        *material = shake.error_material.clone();
+
+        if shake.timer.finished() {
+            transform.translation.x = 0.0;
+            *material = out.0.clone();
+            commands.entity(entity).remove::<PadShake>();
+        }
+    }
+}
+
+fn game_over_system(
+    time: Res<Time>,
+    mut game_over: ResMut<PendingGameOver>,
+    mut next_scene: ResMut<NextScene>,
+) {
+    if let Some(timer) = &mut game_over.0 {
+        if timer.tick(time.delta()).finished() {
+            game_over.0 = None;
+            next_scene.0 = Scene::Score;
+        }
+    }
+}
+
+type TweenQuery<'a> = (
+    Entity,
+    &'a mut Tween,
+    &'a mut Transform,
+    Option<&'a mut Text>,
+    Option<&'a Handle<ColorMaterial>>,
+);
+
+/// Runs on real time so menus still animate while the game is paused
+fn tween_system(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut tweens: Query<TweenQuery>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut completed: EventWriter<TweenCompleted>,
+) {
+    for (entity, mut tween, mut transform, mut text, material) in &mut tweens {
+        tween.elapsed += time.delta_seconds();
+        let t = tween.progress();
+
+        for target in &tween.targets {
+            let color = match *target {
+                TweenTarget::Translation { start, end } => {
+                    transform.translation = start.lerp(end, t);
+                    None
+                }
+                TweenTarget::Scale { start, end } => {
+                    transform.scale = start.lerp(end, t);
+                    None
+                }
+                TweenTarget::Color { start, end } => {
+                    Some(Color::rgba_from_array(Vec4::from(start.as_rgba_f32()).lerp(Vec4::from(end.as_rgba_f32()), t)))
+                }
+                TweenTarget::Alpha { start, end } => {
+                    let alpha = start + (end - start) * t;
+                    if let Some(text) = &mut text {
+                        for section in &mut text.sections {
+                            section.style.color.set_a(alpha);
+                        }
+                    }
+                    if let Some(material) = material.and_then(|handle| materials.get_mut(handle)) {
+                        material.color.set_a(alpha);
+                    }
+                    None
+                }
+            };
+
+            if let Some(color) = color {
+                if let Some(text) = &mut text {
+                    for section in &mut text.sections {
+                        section.style.color = color;
+                    }
+                }
+                if let Some(material) = material.and_then(|handle| materials.get_mut(handle)) {
+                    material.color = color;
+                }
+            }
+        }
+
+        if tween.finished() {
+            commands.entity(entity).remove::<Tween>();
+            completed.send(TweenCompleted { entity });
+        }
+    }
 }
 
-fn setup_click_to_start_scene(
-    window: Query<&Window, With<PrimaryWindow>>,
-    asset_server: Res<AssetServer>,
+/// Hides text once it has faded all the way out, so it's dropped from the
+/// accessibility tree as well as the screen
+fn hide_faded_text_system(
+    mut completed: EventReader<TweenCompleted>,
+    mut texts: Query<(&Text, &mut Visibility)>,
+) {
+    for event in completed.read() {
+        if let Ok((text, mut visibility)) = texts.get_mut(event.entity) {
+            if text.sections.iter().all(|section| section.style.color.a() <= 0.0) {
+                *visibility = Visibility::Hidden;
+            }
+        }
+    }
+}
+
+fn particle_system(
     mut commands: Commands,
+    time: Res<Time>,
+    mut particles: Query<(Entity, &mut Particle, &mut Transform, &Handle<ColorMaterial>)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    let window = window.single();
+    for (entity, mut particle, mut transform, handle) in &mut particles {
+        particle.lifetime.tick(time.delta());
+        transform.translation += (particle.velocity * time.delta_seconds()).extend(0.0);
+        if let Some(material) = materials.get_mut(handle) {
+            material.color.set_a(particle.lifetime.fraction_remaining());
+        }
+        if particle.lifetime.finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn user_game_system(
+    mut commands: Commands,
+    sounds: Res<PatternSounds>,
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut next_scene: ResMut<NextScene>,
+    query: Query<(Entity, &MouseHoverTracker, &PatternIdx)>,
+    mut clicks: EventReader<Clicked>,
+    mut timer: ResMut<PatternAnimationTimer>,
+    mut state: ResMut<GameState>,
+    mut label: Query<(Entity, &mut Text), With<MemorizeLabel>>,
+    settings: Res<Settings>,
+    mode: Res<SelectedMode>,
+    mut game_over: ResMut<PendingGameOver>,
+    mut records: GameRecords,
+    mut announce: EventWriter<Announce>,
+    mut pad_presses: EventWriter<PadPressed>,
+) {
+    let key_pad = pad_key(&keys);
+    let clicked_pad = clicks
+        .read()
+        .find_map(|click| query.get(click.entity).ok().map(|(_entity, _tracker, idx)| (idx.0, click.position)));
+    if state.interactive && game_over.0.is_none() {
+        let Some(button_idx) = key_pad.or(clicked_pad.map(|(pad, _position)| pad)) else {
+            return;
+        };
+        announce.send(Announce(PAD_NAMES[button_idx as usize].to_string()));
+        pad_presses.send(PadPressed {
+            pad: button_idx,
+            // Keyboard presses come from the middle of the pad
+            position: if key_pad.is_some() { None } else { clicked_pad.map(|(_pad, position)| position) },
+            correct: state.awaiting_extension() || button_idx == state.expected_pad(),
+        });
+
+        // Record every press, including the one that ends the run, so that
+        // this run can be raced against later
+        let press = RecordedPress {
+            pad: button_idx,
+            time: (time.elapsed_seconds_f64() - state.round_start) as f32,
+        };
+        if let Some(round) = state.rounds.last_mut() {
+            round.push(press);
+        }
+
+        let mut turn_over = false;
+        if state.awaiting_extension() {
+            // Any pad is fine, it becomes the new end of the pattern
+            commands.spawn(AudioBundle {
+                source: match button_idx {
+                    0 => sounds.0.clone(),
+                    1 => sounds.1.clone(),
+                    2 => sounds.2.clone(),
+                    _ => sounds.3.clone(),
+                },
+                settings: PlaybackSettings::DESPAWN,
+            });
+            state.pattern.push(button_idx);
+            state.max_idx += 1;
+            turn_over = true;
+        } else if button_idx == state.expected_pad() {
+            // We pressed the right button
+            commands.spawn(AudioBundle {
+                source: match button_idx {
+                    0 => sounds.0.clone(),
+                    1 => sounds.1.clone(),
+                    2 => sounds.2.clone(),
+                    _ => sounds.3.clone(),
+                },
+                settings: PlaybackSettings::DESPAWN,
+            });
+            if state.add_one {
+                state.idx += 1;
+                if state.awaiting_extension() {
+                    for (entity_id, mut text) in &mut label {
+                        text.sections[0].value = ADD_ONE_TEXT.to_string();
+                        commands.entity(entity_id).insert((Visibility::Visible, Tween::fade_in(LABEL_FADE_SECONDS)));
+                    }
+                    announce.send(Announce(ADD_ONE_TEXT.to_string()));
+                }
+            } else if state.idx == state.max_idx {
+                // In a hot seat game the round only grows once everyone still
+                // in has had their turn
+                if state.players.is_empty() || state.advance_player() {
+                    state.max_idx += 1;
+                }
+                turn_over = true;
+            } else {
+                state.idx += 1;
+            }
+        } else {
+            // We pressed the wrong button
+            let settings = PlaybackSettings::DESPAWN;
+            commands.spawn(AudioBundle {settings, source: sounds.0.clone()});
+            commands.spawn(AudioBundle {settings, source: sounds.1.clone()});
+            commands.spawn(AudioBundle {settings, source: sounds.2.clone()});
+            commands.spawn(AudioBundle {settings, source: sounds.3.clone()});
+            announce.send(Announce("Wrong".to_string()));
+
+            // Let the wrong pad finish shaking before leaving the game
+            if state.players.is_empty() {
+                game_over.0 = Some(Timer::from_seconds(GAME_OVER_DELAY_SECONDS, TimerMode::Once));
+                // A lost game can't be continued, even if the app closes
+                // before the score screen
+                let missed_pad = state.expected_pad();
+                records.game_over(mode.0, &state, Some(missed_pad));
+            } else {
+                let current = state.current_player;
+                state.players[current].eliminated = Some(state.max_idx);
+
+                if state.players_remaining() <= 1 {
+                    game_over.0 = Some(Timer::from_seconds(GAME_OVER_DELAY_SECONDS, TimerMode::Once));
+                } else {
+                    if state.advance_player() {
+                        state.max_idx += 1;
+                    }
+                    turn_over = true;
+                }
+            }
+        }
+
+        if state.length_limit.is_some_and(|limit| state.max_idx >= limit) {
+            records.game_over(mode.0, &state, None);
+            next_scene.0 = Scene::Score;
+        } else if turn_over {
+            records.round_complete(mode.0, &state);
+            state.idx = 0;
+            state.interactive = false;
+            if state.speed_up {
+                timer.0.set_duration(Duration::from_secs_f32(rapid_step_seconds(state.max_idx, settings.reduced_flash)));
+            }
+            timer.0.reset();
+            for (entity_id, mut text) in &mut label {
+                text.sections[0].value = state.memorize_text();
+                commands.entity(entity_id).insert((Visibility::Visible, Tween::fade_in(LABEL_FADE_SECONDS)));
+            }
+            announce.send(Announce(state.memorize_text()));
+            for (entity_id, _tracker, _idx) in &query {
+                commands.entity(entity_id).insert(MouseHoverDisable);
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn setup_score(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    state: Res<GameState>,
+    mode: Res<SelectedMode>,
+    mut best_run: ResMut<BestRun>,
+    mut old_high_score: ResMut<OldHighScore>,
+    mut high_scores: ResMut<HighScores>,
+    mut sprint_record: ResMut<SprintRecord>,
+    mut resumable: ResMut<ResumableGame>,
+    settings: Res<Settings>,
+    mut announce: EventWriter<Announce>,
+) {
+    resumable.clear(mode.0);
 
     let font = asset_server.load("fonts/FiraSans-Bold.ttf");
     let text_style = TextStyle {
         font: font.clone(),
-        font_size: 60.0,
+        font_size: 80.0,
         color: Color::BLACK,
     };
     let text_justification = JustifyText::Center;
 
-    commands.spawn((
-        Transform::from_xyz(0.0, 0.0, 0.0),
-        MouseHoverTracker::from_rect(99999., 99999.),
-        SceneChangeButton {
-            width: 99999.,
-            height: 99999.,
-            scene: Scene::MainMenu,
-        },
-        SceneObject(()),
-    ));
+    announce.send(Announce(format!("Game over. Score: {}", state.max_idx)));
 
-    commands.spawn((
-        Text2dBundle {
-            text: Text::from_section("Click anywhere to begin", text_style.clone())
-                .with_justify(text_justification),
-            transform: Transform::from_xyz(0.0, 0.0, 1.0),
-            ..default()
-        },
-        SceneObject(()),
-    ));
+    if mode.0 == GameMode::Ghost {
+        if let Some(ghost) = &best_run.0 {
+            spawn_ghost_results(&mut commands, &font, &state.recording(), ghost);
+        }
+    } else if !state.players.is_empty() {
+        spawn_hot_seat_results(&mut commands, &font, &state);
+    } else if mode.0 == GameMode::Sprint {
+        spawn_sprint_results(&mut commands, &font, &state, &mut sprint_record);
+    } else {
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(format!("Score: {}", state.max_idx), text_style.clone())
+                    .with_justify(text_justification),
+                transform: Transform::from_xyz(0.0, 0.0, 0.0),
+                ..default()
+            },
+            Tween::new(SCORE_POP_SECONDS, Ease::BackOut)
+                .with(TweenTarget::Scale { start: Vec3::splat(0.25), end: Vec3::ONE }),
+            SceneObject(()),
+        ));
+
+        if state.speed_up {
+            let small_text_style = TextStyle {
+                font: font.clone(),
+                font_size: 40.0,
+                color: Color::BLACK,
+            };
+            commands.spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        format!("Top speed: {:.2}s per pad", rapid_step_seconds(state.max_idx, settings.reduced_flash)),
+                        small_text_style,
+                    )
+                        .with_justify(text_justification),
+                    transform: Transform::from_xyz(0.0, -150.0, 0.0),
+                    ..default()
+                },
+                SceneObject(()),
+            ));
+        }
+
+        if let Some(high_score) = high_scores.0.get_mut(&mode.0) {
+            if state.max_idx > *high_score {
+                old_high_score.0 = *high_score;
+                *high_score = state.max_idx;
+                save_high_score(mode.0, state.max_idx);
+
+                // The ghost is always the best classic run. A resumed run is
+                // missing its earlier rounds so it can't be raced.
+                if mode.0 == GameMode::Classic && !state.resumed {
+                    let recording = state.recording();
+                    save_item(BEST_RUN_KEY, &recording.to_save_string());
+                    best_run.0 = Some(recording);
+                }
+
+                commands.spawn((
+                    Text2dBundle {
+                        text: Text::from_section("NEW HIGH SCORE!", text_style.clone())
+                            .with_justify(text_justification),
+                        transform: Transform::from_xyz(0.0, 80.0, 0.0),
+                        ..default()
+                    },
+                    Tween::new(SCORE_POP_SECONDS, Ease::CubicOut)
+                        .with(TweenTarget::Translation { start: Vec3::new(0.0, 160.0, 0.0), end: Vec3::new(0.0, 80.0, 0.0) })
+                        .with(TweenTarget::Color { start: Color::BLACK, end: NEW_HIGH_SCORE_COLOR })
+                        .with_delay(SCORE_POP_SECONDS),
+                    SceneObject(()),
+                ));
+                commands.spawn((
+                    Text2dBundle {
+                        text: Text::from_section(format!("Old High Score: {}", old_high_score.0), text_style.clone())
+                            .with_justify(text_justification),
+                        transform: Transform::from_xyz(0.0, -80.0, 0.0),
+                        ..default()
+                    },
+                    Tween::fade_in(SCORE_POP_SECONDS).with_delay(SCORE_POP_SECONDS),
+                    SceneObject(()),
+                ));
+            } else {
+                commands.spawn((
+                    Text2dBundle {
+                        text: Text::from_section(format!("High Score: {}", high_score), text_style.clone())
+                            .with_justify(text_justification),
+                        transform: Transform::from_xyz(0.0, -80.0, 0.0),
+                        ..default()
+                    },
+                    Tween::fade_in(SCORE_POP_SECONDS).with_delay(SCORE_POP_SECONDS),
+                    SceneObject(()),
+                ));
+            }
+        }
+    }
+
+    commands.spawn((menu_root(JustifyContent::FlexEnd), SceneObject(()))).with_children(|menu| {
+        add_button(
+            menu,
+            &font,
+            "Click to return",
+            Color::hsl(235.0, 0.95, 0.7),
+            Color::hsl(235.0, 0.95, 0.3),
+            ButtonAction::ChangeScene(Scene::MainMenu),
+        );
+    });
 }
 
-fn setup_main_menu(
-    window: Query<&Window, With<PrimaryWindow>>,
-    high_score: Res<HighScore>,
-    asset_server: Res<AssetServer>,
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
-    let window = window.single();
+const SCORE_POP_SECONDS: f32 = 0.4;
 
-    add_scene_change_button(
-        &asset_server,
-        &mut commands,
-        &mut materials,
-        &mut meshes,
-        "Start Game",
-        Color::rgb_u8(0, 228, 48),
-        Color::rgb_u8(0, 117, 44),
-        275.0,
-        60.0,
-        Transform::from_xyz(0.0, 0.0, 0.0),
-        Scene::Game,
-    );
+const NEW_HIGH_SCORE_COLOR: Color = Color::rgb(0.8, 0.1, 0.5);
 
-    // Credits button
-    add_scene_change_button(
-        &asset_server,
-        &mut commands,
-        &mut materials,
-        &mut meshes,
-        "Credits",
-        Color::rgb_u8(0, 121, 241),
-        Color::rgb_u8(0, 82, 172),
-        180.0,
-        60.0,
-        Transform::from_xyz(0.0, -80.0, 0.0),
-        Scene::Credits,
-    );
+fn format_millis(millis: u32) -> String {
+    format!("{}.{:03}s", millis / 1000, millis % 1000)
+}
 
-    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+fn spawn_sprint_results(
+    commands: &mut Commands,
+    font: &Handle<Font>,
+    state: &GameState,
+    record: &mut SprintRecord,
+) {
     let text_style = TextStyle {
         font: font.clone(),
         font_size: 80.0,
         color: Color::BLACK,
     };
+    let text_justification = JustifyText::Center;
 
-    let bl = Vec2::new(-window.resolution.width()/2., -window.resolution.height()/2.);
+    let mut lines = Vec::new();
+    if state.max_idx >= SPRINT_LENGTH {
+        let millis = (state.input_time() * 1000.0).round() as u32;
+        lines.push((0.0, format!("Time: {}", format_millis(millis))));
+
+        match record.0 {
+            Some(old) if old <= millis => {
+                lines.push((-80.0, format!("Record: {}", format_millis(old))));
+            }
+            old => {
+                record.0 = Some(millis);
+                save_item(SPRINT_RECORD_KEY, &millis.to_string());
+                lines.push((80.0, "NEW RECORD!".to_string()));
+                if let Some(old) = old {
+                    lines.push((-80.0, format!("Old Record: {}", format_millis(old))));
+                }
+            }
+        }
+    } else {
+        lines.push((0.0, format!("Failed at pad {} of {SPRINT_LENGTH}", state.idx + 1)));
+        let record_text = match record.0 {
+            Some(millis) => format!("Record: {}", format_millis(millis)),
+            None => "No record yet".to_string(),
+        };
+        lines.push((-80.0, record_text));
+    }
+
+    for (y, line) in lines {
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(line, text_style.clone())
+                    .with_justify(text_justification),
+                transform: Transform::from_xyz(0.0, y, 0.0),
+                ..default()
+            },
+            SceneObject(()),
+        ));
+    }
+}
+
+/// The most rounds we have room to compare on the score screen
+const GHOST_RESULT_ROWS: usize = 8;
+
+fn spawn_ghost_results(
+    commands: &mut Commands,
+    font: &Handle<Font>,
+    player: &RunRecording,
+    ghost: &RunRecording,
+) {
+    let text_style = TextStyle {
+        font: font.clone(),
+        font_size: 60.0,
+        color: Color::BLACK,
+    };
+    let small_text_style = TextStyle {
+        font: font.clone(),
+        font_size: 30.0,
+        color: Color::BLACK,
+    };
+    let text_justification = JustifyText::Center;
+
+    let player_score = player.score();
+    let ghost_score = ghost.score();
+
+    let verdict = match player_score.cmp(&ghost_score) {
+        std::cmp::Ordering::Greater => "You beat the ghost!",
+        std::cmp::Ordering::Less => "The ghost got further",
+        std::cmp::Ordering::Equal => "Tied with the ghost",
+    };
 
     commands.spawn((
         Text2dBundle {
-            text: Text::from_section(&format!("High Score: {}", high_score.0), text_style.clone()),
-            text_anchor: Anchor::BottomLeft,
-            transform: Transform::from_xyz(bl.x+10., bl.y, 0.0),
+            text: Text::from_section(verdict, text_style.clone())
+                .with_justify(text_justification),
+            transform: Transform::from_xyz(0.0, 260.0, 0.0),
+            ..default()
+        },
+        SceneObject(()),
+    ));
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(format!("You: {player_score}   Ghost: {ghost_score}"), text_style.clone())
+                .with_justify(text_justification),
+            transform: Transform::from_xyz(0.0, 190.0, 0.0),
             ..default()
         },
         SceneObject(()),
     ));
+
+    // Compare the rounds both of you completed, most recent last
+    let shared_rounds = player_score.min(ghost_score) as usize;
+    let mut player_wins = 0;
+    let mut lines = Vec::new();
+    for round in 0..shared_rounds {
+        let (Some(player_time), Some(ghost_time)) = (player.round_time(round), ghost.round_time(round)) else {
+            continue;
+        };
+        let winner = if player_time <= ghost_time {
+            player_wins += 1;
+            "You"
+        } else {
+            "Ghost"
+        };
+        lines.push(format!(
+            "Round {}: You {:.2}s, Ghost {:.2}s - {winner}",
+            round + 1,
+            player_time,
+            ghost_time,
+        ));
+    }
+
+    if shared_rounds > 0 {
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(format!("You were faster in {player_wins} of {shared_rounds} rounds"), small_text_style.clone())
+                    .with_justify(text_justification),
+                transform: Transform::from_xyz(0.0, 130.0, 0.0),
+                ..default()
+            },
+            SceneObject(()),
+        ));
+    }
+
+    let skipped = lines.len().saturating_sub(GHOST_RESULT_ROWS);
+    for (row, line) in lines.iter().skip(skipped).enumerate() {
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(line.as_str(), small_text_style.clone())
+                    .with_justify(text_justification),
+                transform: Transform::from_xyz(0.0, 85.0 - 35.0 * row as f32, 0.0),
+                ..default()
+            },
+            SceneObject(()),
+        ));
+    }
 }
 
-fn setup_credits(
-    asset_server: Res<AssetServer>,
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+fn spawn_hot_seat_results(
+    commands: &mut Commands,
+    font: &Handle<Font>,
+    state: &GameState,
 ) {
-    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
     let text_style = TextStyle {
         font: font.clone(),
         font_size: 80.0,
         color: Color::BLACK,
     };
+    let player_text_style = TextStyle {
+        font: font.clone(),
+        font_size: 40.0,
+        color: Color::BLACK,
+    };
     let text_justification = JustifyText::Center;
 
+    let winner = state.players.iter().position(|player| player.eliminated.is_none());
+    let title = match winner {
+        Some(winner) => format!("Player {} wins!", winner + 1),
+        None => "Nobody wins!".to_string(),
+    };
+
     commands.spawn((
         Text2dBundle {
-            text: Text::from_section("Game by Stephen Molyneaux 2024", text_style.clone())
+            text: Text::from_section(title, text_style.clone())
                 .with_justify(text_justification),
-            transform: Transform::from_xyz(0.0, 0.0, 0.0),
+            transform: Transform::from_xyz(0.0, 200.0, 0.0),
             ..default()
         },
-        MouseHoverTracker::from_rect(99999., 99999.),
-        SceneChangeButton {
-            width: 99999.,
-            height: 99999.,
-            scene: Scene::MainMenu,
-        },
         SceneObject(()),
     ));
 
+    for (idx, player) in state.players.iter().enumerate() {
+        let status = if player.eliminated.is_some() { "out" } else { "last standing" };
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    format!("Player {}: {} ({status})", idx + 1, state.player_score(idx)),
+                    player_text_style.clone(),
+                )
+                    .with_justify(text_justification),
+                transform: Transform::from_xyz(0.0, 100.0 - 60.0 * idx as f32, 0.0),
+                ..default()
+            },
+            SceneObject(()),
+        ));
+    }
+}
+
+const BUTTON_POP_SECONDS: f32 = 0.25;
+
+fn button_pop_in() -> Tween {
+    Tween::new(BUTTON_POP_SECONDS, Ease::BackOut)
+        .with(TweenTarget::Scale { start: Vec3::splat(0.8), end: Vec3::ONE })
+}
+
+const BUTTON_FONT_SIZE: f32 = 48.0;
+
+const BUTTON_MIN_WIDTH: f32 = 180.0;
+
+const BUTTON_BORDER_WIDTH: f32 = 4.0;
+
+const BUTTON_FOCUS_COLOR: Color = Color::BLACK;
+
+const BUTTON_DISABLED_COLOR: Color = Color::rgb(0.85, 0.85, 0.85);
+
+const BUTTON_DISABLED_TEXT_COLOR: Color = Color::rgb(0.55, 0.55, 0.55);
+
+const MENU_GAP: f32 = 16.0;
+
+/// Spawns a button sized to fit its text
+fn add_button<'a>(
+    parent: &'a mut ChildBuilder,
+    font: &Handle<Font>,
+    text: &str,
+    color: Color,
+    hover_color: Color,
+    action: ButtonAction,
+) -> EntityCommands<'a> {
     let text_style = TextStyle {
         font: font.clone(),
-        font_size: 40.0,
+        font_size: BUTTON_FONT_SIZE,
         color: Color::BLACK,
     };
-    commands.spawn((
-        Text2dBundle {
-            text: Text::from_section("Created with Bevy", text_style.clone())
-                .with_justify(text_justification),
-            transform: Transform::from_xyz(0.0, -80.0, 0.0),
+
+    let mut button = parent.spawn((
+        ButtonBundle {
+            style: Style {
+                min_width: Val::Px(BUTTON_MIN_WIDTH),
+                padding: UiRect::horizontal(Val::Px(24.0)),
+                border: UiRect::all(Val::Px(BUTTON_BORDER_WIDTH)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: color.into(),
+            border_color: Color::NONE.into(),
             ..default()
         },
-        SceneObject(()),
+        MenuButton {
+            color,
+            hover_color,
+            pressed_color: hover_color * PRESSED_DARKEN,
+            armed: false,
+        },
+        action,
+        button_node(text),
+        button_pop_in(),
     ));
+    button.with_children(|button| {
+        button.spawn(TextBundle::from_section(text, text_style));
+    });
+    button
+}
 
+/// Full window column that menu scenes lay their contents out in
+fn menu_root(justify_content: JustifyContent) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            justify_content,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(MENU_GAP),
+            padding: UiRect::all(Val::Px(24.0)),
+            ..default()
+        },
+        ..default()
+    }
+}
+
+fn menu_title(text: &str, font: &Handle<Font>) -> TextBundle {
     let text_style = TextStyle {
         font: font.clone(),
-        font_size: 60.0,
+        font_size: 80.0,
         color: Color::BLACK,
     };
-    commands.spawn((
-        Text2dBundle {
-            text: Text::from_section("Click to Return", text_style.clone())
-                .with_justify(text_justification),
-            transform: Transform::from_xyz(0.0, -220.0, 0.0),
-            ..default()
-        },
-        SceneObject(()),
-    ));
+    TextBundle::from_section(text, text_style).with_style(Style {
+        margin: UiRect::bottom(Val::Px(MENU_GAP)),
+        ..default()
+    })
 }
 
-fn setup_game(
-    asset_server: Res<AssetServer>,
-    window: Query<&Window, With<PrimaryWindow>>,
-    mut timer: ResMut<PatternAnimationTimer>,
+#[allow(clippy::too_many_arguments)]
+fn handle_scene_change(
+    next_scene: Res<NextScene>,
+    scene_setup_system: Res<SceneSetupSystem>,
+    scene_objects: Query<Entity, With<SceneObject>>,
     mut commands: Commands,
+    mut current_scene: ResMut<CurrentScene>,
+    mut focus: ResMut<Focus>,
+    mut paused: ResMut<Paused>,
+    mut time: ResMut<Time<Virtual>>,
     mut state: ResMut<GameState>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    let window = window.single();
+    // Check if we're updating the scene
+    if next_scene.0 != current_scene.0 {
+        let scene = next_scene.0;
+        if current_scene.0 == Scene::Game {
+            // The score screen still reads the finished game, but it mustn't
+            // take any more input
+            state.interactive = false;
+        }
+        current_scene.0 = scene;
+        focus.0 = None;
 
-    timer.0.reset();
-    state.reset();
-    for idx in 0..255 {
-        state.pattern.push(rand::random::<u8>() % 4);
+        // A paused game is left behind along with its pause menu
+        paused.0 = false;
+        time.unpause();
+
+        println!("Switching to {scene:?}");
+
+        // Remove any scene-specific entities
+        println!("Removing scene objects");
+        for obj in &scene_objects {
+            commands.entity(obj).despawn_recursive();
+        }
+
+        // Run the setup system for the new scene
+        if let Some(system) = scene_setup_system.system_map.get(&scene) {
+            commands.run_system(*system);
+        } else {
+            println!("NOTE: Transitioning to scene {scene:?} which does not have a setup system");
+        }
     }
-    state.max_idx = 0;
+}
 
-    // Create 4 touch areas
-    let red = Color::hsl(0.0, 0.95, 0.9);
-    let hover_red = Color::hsl(0.0, 0.95, 0.8);
-    let button_red = Color::hsl(0.0, 0.95, 0.4);
-    let green = Color::hsl(115.0, 0.95, 0.9);
-    let hover_green = Color::hsl(115.0, 0.95, 0.8);
-    let blue = Color::hsl(235.0, 0.95, 0.9);
-    let hover_blue = Color::hsl(235.0, 0.95, 0.8);
-    let yellow = Color::hsl(60.0, 0.95, 0.9);
-    let hover_yellow = Color::hsl(60.0, 0.95, 0.8);
+/// Windows and cameras can come and go at any point, so anything missing is
+/// skipped rather than expected
+fn update_pointer_positions(
+    primary_window: Query<Entity, With<PrimaryWindow>>,
+    windows: Query<&Window>,
+    cameras: Query<(Entity, &Camera, &GlobalTransform, Option<&RenderLayers>)>,
+    mut pointer_positions: ResMut<PointerPositions>,
+) {
+    let primary_window = primary_window.get_single().ok();
 
-    let center = Vec2::new(0., 0.);
-    let tl = Vec2::new(-window.resolution.width()/2., window.resolution.height()/2.);
-    let tr = Vec2::new(window.resolution.width()/2., window.resolution.height()/2.);
-    let bl = Vec2::new(-window.resolution.width()/2., -window.resolution.height()/2.);
-    let br = Vec2::new(window.resolution.width()/2., -window.resolution.height()/2.);
+    let mut pointers = Vec::new();
+    for (entity, camera, camera_transform, layers) in &cameras {
+        if !camera.is_active {
+            continue;
+        }
+        let Some(NormalizedRenderTarget::Window(window_ref)) = camera.target.normalize(primary_window) else {
+            continue;
+        };
+        let Some(cursor) = windows.get(window_ref.entity()).ok().and_then(Window::cursor_position) else {
+            continue;
+        };
+        let Some(viewport) = camera.logical_viewport_rect() else {
+            continue;
+        };
+        if !viewport.contains(cursor) {
+            continue;
+        }
+        if let Some(world_position) = camera.viewport_to_world_2d(camera_transform, cursor - viewport.min) {
+            pointers.push(CameraPointer {
+                camera: entity,
+                order: camera.order,
+                layers: layers.copied().unwrap_or_default(),
+                world_position,
+            });
+        }
+    }
+    pointers.sort_by_key(|pointer| (std::cmp::Reverse(pointer.order), pointer.camera));
 
-    // Red button
-    commands.spawn((
-        MaterialMesh2dBundle {
-            mesh: Mesh2dHandle(
-                meshes.add(
-                    Triangle2d::new(center, tl, tr)
-                )
-            ),
-            material: materials.add(red),
-            transform: Transform::from_xyz(0., 0., 0.),
-            ..default()
-        },
-        MouseHoverDisable,
-        MouseHoverTracker::from_triangle(center, tl, tr),
-        MouseOverMaterial(materials.add(hover_red)),
-        MouseOutMaterial(materials.add(red)),
-        PatternIdx(0),
-        SceneObject(()),
-    ));
+    pointer_positions.set_if_neq(PointerPositions { pointers });
+}
 
-    // Green button
-    commands.spawn((
-        MaterialMesh2dBundle {
-            mesh: Mesh2dHandle(
-                meshes.add(
-                    Triangle2d::new(center, tr, br)
-                )
-            ),
-            material: materials.add(green),
-            transform: Transform::from_xyz(0., 0., 0.),
-            ..default()
-        },
-        MouseHoverDisable,
-        MouseHoverTracker::from_triangle(center, tr, br),
-        MouseOverMaterial(materials.add(hover_green)),
-        MouseOutMaterial(materials.add(green)),
-        PatternIdx(1),
-        SceneObject(()),
-    ));
+fn update_mouse_hover_disable(
+    mut tracked_objects: Query<(Entity, &mut MouseHoverTracker), With<MouseHoverDisable>>,
+    mut exits: EventWriter<HoverExit>,
+) {
+    for (entity, mut tracker) in &mut tracked_objects {
+        if tracker.set_hovered(false) {
+            exits.send(HoverExit { entity });
+        }
+    }
+}
 
-    // Blue button
-    commands.spawn((
-        MaterialMesh2dBundle {
-            mesh: Mesh2dHandle(
-                meshes.add(
-                    Triangle2d::new(center, bl, br)
-                )
-            ),
-            material: materials.add(blue),
-            transform: Transform::from_xyz(0., 0., 0.),
-            ..default()
-        },
-        MouseHoverDisable,
-        MouseHoverTracker::from_triangle(center, bl, br),
-        MouseOverMaterial(materials.add(hover_blue)),
-        MouseOutMaterial(materials.add(blue)),
-        PatternIdx(2),
-        SceneObject(()),
-    ));
+type HoverCacheQuery<'a> = (Entity, &'a MouseHoverTracker, &'a GlobalTransform, Option<&'a mut HoverCache>);
+type HoverCacheFilter = Or<(Changed<GlobalTransform>, Added<MouseHoverTracker>)>;
 
-    // Yellow button
-    commands.spawn((
-        MaterialMesh2dBundle {
-            mesh: Mesh2dHandle(
-                meshes.add(
-                    Triangle2d::new(center, tl, bl)
-                )
-            ),
-            material: materials.add(yellow),
-            transform: Transform::from_xyz(0., 0., 0.),
-            ..default()
-        },
-        MouseHoverDisable,
-        MouseHoverTracker::from_triangle(center, tl, bl),
-        MouseOverMaterial(materials.add(hover_yellow)),
-        MouseOutMaterial(materials.add(yellow)),
-        PatternIdx(3),
-        SceneObject(()),
-    ));
+pub fn update_hover_cache(
+    mut commands: Commands,
+    mut trackers: Query<HoverCacheQuery, HoverCacheFilter>,
+) {
+    for (entity, tracker, transform, cache) in &mut trackers {
+        match cache {
+            Some(mut cache) => *cache = HoverCache::new(tracker, transform),
+            None => {
+                commands.entity(entity).insert(HoverCache::new(tracker, transform));
+            }
+        }
+    }
+}
 
-    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
-    let text_style = TextStyle {
-        font: font.clone(),
-        font_size: 80.0,
-        color: Color::BLACK,
-    };
-    let text_justification = JustifyText::Center;
+/// The grid is rebuilt from scratch, but only on frames where a tracker was
+/// added, moved or removed
+pub fn rebuild_hover_grid(
+    changed: Query<(), Changed<HoverCache>>,
+    mut removed: RemovedComponents<MouseHoverTracker>,
+    caches: Query<(Entity, &HoverCache)>,
+    mut grid: ResMut<HoverGrid>,
+) {
+    let any_removed = removed.read().count() > 0;
+    if changed.is_empty() && !any_removed {
+        return;
+    }
 
-    commands.spawn((
-        Text2dBundle {
-            text: Text::from_section("Memorize", text_style.clone())
-                .with_justify(text_justification),
-            transform: Transform::from_xyz(0.0, 0.0, 1.0),
-            ..default()
-        },
-        MemorizeLabel,
-        SceneObject(()),
-    ));
+    grid.cells.clear();
+    grid.everywhere.clear();
+    for (entity, cache) in &caches {
+        grid.insert(entity, cache.bounds);
+    }
 }
 
-fn pattern_playback_system(
-    mut commands: Commands,
-    sounds: Res<PatternSounds>,
-    time: Res<Time>,
-    mut query: Query<(Entity, &PatternIdx, &MouseOverMaterial, &MouseOutMaterial, &mut Handle<ColorMaterial>)>,
-    mut label: Query<Entity, With<MemorizeLabel>>,
-    mut timer: ResMut<PatternAnimationTimer>,
-    mut state: ResMut<GameState>,
+/// Only the topmost tracker under the cursor is hovered, along with anything
+/// above it that lets the cursor pass through. Hovering a tracker also hovers
+/// any trackers it's a child of.
+///
+/// Trackers are hovered through the topmost camera that sees their render
+/// layers, so with split-screen each viewport hovers its own trackers.
+///
+/// Nothing is tested unless the cursor, a camera, a tracker or which trackers
+/// are disabled changed since the last frame.
+#[allow(clippy::too_many_arguments)]
+pub fn update_mouse_hover_state(
+    pointers: Res<PointerPositions>,
+    grid: Res<HoverGrid>,
+    mut tracked_objects: Query<(&mut MouseHoverTracker, &HoverCache, Option<&RenderLayers>), Without<MouseHoverDisable>>,
+    newly_disabled: Query<(), Added<MouseHoverDisable>>,
+    mut newly_enabled: RemovedComponents<MouseHoverDisable>,
+    parents: Query<&Parent>,
+    mut hovered_before: Local<Vec<Entity>>,
+    mut enters: EventWriter<HoverEnter>,
+    mut exits: EventWriter<HoverExit>,
 ) {
-    if !state.interactive {
-        if timer.0.tick(time.delta()).just_finished() {
-            println!("PB system timer just finished");
-            if state.idx > state.max_idx {
-                state.interactive = true;
-                state.idx = 0;
-                for (entity_id, idx, over, out, mut mat) in &mut query {
-                    *mat = out.0.clone();
-                    commands.entity(entity_id).remove::<MouseHoverDisable>();
-                }
-                for entity_id in &label {
-                    commands.entity(entity_id).insert(Visibility::Hidden);
-                }
-            } else {
-                if !state.pattern.is_empty() {
-                    println!(
-                        "Playing sound {} for idx {}",
-                        state.pattern[state.idx as usize],
-                        state.idx,
-                    );
-                    commands.spawn(AudioBundle {
-                        source: match state.pattern[state.idx as usize] {
-                            0 => sounds.0.clone(),
-                            1 => sounds.1.clone(),
-                            2 => sounds.2.clone(),
-                            _ => sounds.3.clone(),
-                        },
-                        settings: PlaybackSettings::DESPAWN,
-                    });
-                }
-                for (entity_id, idx, over, out, mut mat) in &mut query {
-                    if state.pattern[state.idx as usize] == idx.0 {
-                        *mat = over.0.clone();
-                    } else {
-                        *mat = out.0.clone();
-                    }
-                }
-                state.idx += 1;
+    let disabled_changed = newly_enabled.read().count() > 0 || !newly_disabled.is_empty();
+    if !pointers.is_changed() && !grid.is_changed() && !disabled_changed {
+        return;
+    }
+
+    let mut hits: Vec<(Entity, i32, f32, bool)> = Vec::new();
+    for (camera_idx, pointer) in pointers.pointers.iter().enumerate() {
+        let mouse_pos = pointer.world_position;
+        for entity in grid.candidates(mouse_pos) {
+            let Ok((tracker, cache, layers)) = tracked_objects.get(entity) else {
+                continue;
+            };
+            // Only hit through the topmost camera that sees the tracker
+            let layers = layers.copied().unwrap_or_default();
+            let first_camera = pointers.pointers.iter().position(|pointer| pointer.layers.intersects(&layers));
+            if first_camera != Some(camera_idx) {
+                continue;
+            }
+            if cache.bounds.contains(mouse_pos) && tracker.shape.contains(cache.local_position(mouse_pos)) {
+                hits.push((entity, tracker.priority, cache.z, tracker.pass_through));
+            }
+        }
+    }
+    // A stable sort keeps trackers seen by higher cameras first among equals
+    hits.sort_by(|(_, a_priority, a_z, _), (_, b_priority, b_z, _)| {
+        b_priority.cmp(a_priority).then(b_z.total_cmp(a_z))
+    });
+
+    let mut hovered = Vec::new();
+    for (entity, _priority, _z, pass_through) in hits {
+        hovered.push(entity);
+        if !pass_through {
+            break;
+        }
+    }
+
+    for entity in hovered.clone() {
+        for ancestor in parents.iter_ancestors(entity) {
+            if tracked_objects.contains(ancestor) && !hovered.contains(&ancestor) {
+                hovered.push(ancestor);
+            }
+        }
+    }
+
+    // Only the trackers that were or are now hovered can have changed
+    for &entity in hovered_before.iter().filter(|entity| !hovered.contains(entity)) {
+        if let Ok((mut tracker, _cache, _layers)) = tracked_objects.get_mut(entity) {
+            if tracker.set_hovered(false) {
+                exits.send(HoverExit { entity });
             }
         }
     }
+    for &entity in &hovered {
+        if let Ok((mut tracker, cache, layers)) = tracked_objects.get_mut(entity) {
+            if tracker.set_hovered(true) {
+                let position = pointers.local_position(cache, layers);
+                enters.send(HoverEnter { entity, position });
+            }
+        }
+    }
+    *hovered_before = hovered;
 }
 
-fn user_game_system(
-    mut commands: Commands,
-    sounds: Res<PatternSounds>,
+/// Clicks need the press and the release on the same tracker, so dragging
+/// onto something doesn't activate it
+fn update_mouse_press_state(
     mouse: Res<ButtonInput<MouseButton>>,
-    mouse_pos: Res<ShmMousePosition>,
-    mut next_scene: ResMut<NextScene>,
-    mut query: Query<(Entity, &MouseHoverTracker, &PatternIdx)>,
-    mut timer: ResMut<PatternAnimationTimer>,
-    mut state: ResMut<GameState>,
-    mut label: Query<Entity, With<MemorizeLabel>>,
+    pointers: Res<PointerPositions>,
+    mut tracked_objects: Query<(Entity, &mut MouseHoverTracker, &HoverCache, Option<&RenderLayers>)>,
+    mut presses: EventWriter<Pressed>,
+    mut clicks: EventWriter<Clicked>,
 ) {
-    if state.interactive && mouse.just_released(MouseButton::Left) {
-        let mut button_idx = None;
-        for (_entity, tracker, idx) in &query {
-            if tracker.is_hovered {
-                button_idx = Some(idx.0);
-                break;
+    if !mouse.any_just_pressed([MouseButton::Left]) && !mouse.any_just_released([MouseButton::Left]) {
+        return;
+    }
+    for (entity, mut tracker, cache, layers) in &mut tracked_objects {
+        let position = pointers.local_position(cache, layers);
+        if mouse.just_pressed(MouseButton::Left) {
+            tracker.is_pressed = tracker.is_hovered;
+            if tracker.is_pressed {
+                presses.send(Pressed { entity, position });
+            }
+        }
+        if mouse.just_released(MouseButton::Left) {
+            if tracker.is_held() {
+                clicks.send(Clicked { entity, position });
             }
+            tracker.is_pressed = false;
         }
+    }
+}
 
-        if button_idx.is_none() {
-            return;
+/// Runs after the hover materials so a held tracker stays pressed when the
+/// cursor comes back onto it
+fn update_mouse_pressed_material(
+    mut presses: EventReader<Pressed>,
+    mut enters: EventReader<HoverEnter>,
+    mut clicks: EventReader<Clicked>,
+    mut query: Query<
+        (&MouseHoverTracker, &MousePressedMaterial, &MouseOverMaterial, &mut Handle<ColorMaterial>),
+        Without<MouseHoverDisable>,
+    >,
+) {
+    for press in presses.read() {
+        if let Ok((_tracker, pressed, _over, mut material)) = query.get_mut(press.entity) {
+            *material = pressed.0.clone();
         }
+    }
+    for enter in enters.read() {
+        if let Ok((tracker, pressed, _over, mut material)) = query.get_mut(enter.entity) {
+            if tracker.is_held() {
+                *material = pressed.0.clone();
+            }
+        }
+    }
+    for click in clicks.read() {
+        if let Ok((_tracker, _pressed, over, mut material)) = query.get_mut(click.entity) {
+            *material = over.0.clone();
+        }
+    }
+}
 
-        let button_idx = button_idx.unwrap();
+fn update_mouse_hover_material(
+    mut enters: EventReader<HoverEnter>,
+    mut query: Query<(&MouseOverMaterial, &mut Handle<ColorMaterial>)>,
+) {
+    for enter in enters.read() {
+        if let Ok((material_info, mut material)) = query.get_mut(enter.entity) {
+            *material = material_info.0.clone();
+        }
+    }
+}
 
-        if button_idx == state.pattern[state.idx as usize] {
-            // We pressed the right button
-            commands.spawn(AudioBundle {
-                source: match button_idx {
-                    0 => sounds.0.clone(),
-                    1 => sounds.1.clone(),
-                    2 => sounds.2.clone(),
-                    _ => sounds.3.clone(),
-                },
-                settings: PlaybackSettings::DESPAWN,
-            });
-            if state.idx == state.max_idx {
-                state.idx = 0;
-                state.max_idx += 1;
-                state.interactive = false;
-                timer.0.reset();
-                for entity_id in &label {
-                    commands.entity(entity_id).insert(Visibility::Visible);
+fn update_mouse_unhover_material(
+    mut exits: EventReader<HoverExit>,
+    mut query: Query<(&MouseOutMaterial, &mut Handle<ColorMaterial>)>,
+) {
+    for exit in exits.read() {
+        if let Ok((material_info, mut material)) = query.get_mut(exit.entity) {
+            *material = material_info.0.clone();
+        }
+    }
+}
+
+type MenuButtonQuery<'a> = (
+    Entity,
+    &'a Interaction,
+    &'a mut MenuButton,
+    &'a mut BackgroundColor,
+    &'a mut BorderColor,
+    &'a mut AccessibilityNode,
+    &'a Children,
+    Has<DisabledButton>,
+);
+
+fn menu_button_system(
+    mut buttons: Query<MenuButtonQuery>,
+    mut texts: Query<&mut Text>,
+    keys: Res<ButtonInput<KeyCode>>,
+    focus: Res<Focus>,
+    mut activated: EventWriter<ButtonActivated>,
+) {
+    for (entity, interaction, mut button, mut background, mut border, mut node, children, disabled) in &mut buttons {
+        if disabled != node.is_disabled() {
+            if disabled {
+                node.set_disabled();
+            } else {
+                node.clear_disabled();
+            }
+            let text_color = if disabled { BUTTON_DISABLED_TEXT_COLOR } else { Color::BLACK };
+            for child in children {
+                if let Ok(mut text) = texts.get_mut(*child) {
+                    for section in &mut text.sections {
+                        section.style.color = text_color;
+                    }
                 }
-                for (entity_id, _tracker, _idx) in &query {
-                    commands.entity(entity_id).insert(MouseHoverDisable);
+            }
+        }
+
+        *border = if focus.0 == Some(entity) { BUTTON_FOCUS_COLOR } else { Color::NONE }.into();
+
+        if disabled {
+            button.armed = false;
+            *background = BUTTON_DISABLED_COLOR.into();
+            continue;
+        }
+
+        *background = match interaction {
+            Interaction::Pressed => {
+                button.armed = true;
+                button.pressed_color
+            }
+            Interaction::Hovered => {
+                // Bevy moves a released button back to hovered if the
+                // cursor is still over it
+                if button.armed {
+                    button.armed = false;
+                    activated.send(ButtonActivated(entity));
                 }
-            } else {
-                state.idx += 1;
+                button.hover_color
+            }
+            Interaction::None => {
+                button.armed = false;
+                button.color
+            }
+        }.into();
+
+        if keyboard_activated(&keys, &focus, entity) {
+            activated.send(ButtonActivated(entity));
+        }
+    }
+}
+
+/// Buttons that aren't part of a UI layout, like the click anywhere areas,
+/// use the world space hover tracking instead
+fn world_button_system(
+    buttons: Query<Entity, (With<MouseHoverTracker>, With<AccessibilityNode>)>,
+    mut clicks: EventReader<Clicked>,
+    keys: Res<ButtonInput<KeyCode>>,
+    focus: Res<Focus>,
+    mut activated: EventWriter<ButtonActivated>,
+) {
+    for click in clicks.read() {
+        if buttons.contains(click.entity) {
+            activated.send(ButtonActivated(click.entity));
+        }
+    }
+    for entity in &buttons {
+        if keyboard_activated(&keys, &focus, entity) {
+            activated.send(ButtonActivated(entity));
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn button_action_system(
+    mut commands: Commands,
+    mut activated: EventReader<ButtonActivated>,
+    mut buttons: Query<(&ButtonAction, Option<&Children>, &mut AccessibilityNode)>,
+    mut texts: Query<&mut Text>,
+    mut settings: ResMut<Settings>,
+    mut next_scene: ResMut<NextScene>,
+    mut mode: ResMut<SelectedMode>,
+    mut announce: EventWriter<Announce>,
+    mut quit: EventWriter<QuitRequested>,
+) {
+    for event in activated.read() {
+        let Ok((action, children, mut node)) = buttons.get_mut(event.0) else {
+            continue;
+        };
+        println!("Button action {action:?}");
+        match *action {
+            ButtonAction::ChangeScene(scene) => next_scene.0 = scene,
+            ButtonAction::StartMode(game_mode) => {
+                mode.0 = game_mode;
+                next_scene.0 = Scene::Game;
+            }
+            ButtonAction::ToggleSetting(setting) => {
+                setting.cycle(&mut settings);
+                settings.save();
+
+                let label = setting.label(&settings);
+                set_button_label(children, &mut texts, &mut node, &label);
+                announce.send(Announce(label));
             }
-        } else {
-            // We pressed the wrong button
-            let settings = PlaybackSettings::DESPAWN;
-            commands.spawn(AudioBundle {settings, source: sounds.0.clone()});
-            commands.spawn(AudioBundle {settings, source: sounds.1.clone()});
-            commands.spawn(AudioBundle {settings, source: sounds.2.clone()});
-            commands.spawn(AudioBundle {settings, source: sounds.3.clone()});
+            ButtonAction::RunSystem(system) => commands.run_system(system),
+            ButtonAction::Quit => {
+                quit.send(QuitRequested);
+            }
+        }
+    }
+}
 
-            next_scene.0 = Scene::Score;
+fn set_button_label(
+    children: Option<&Children>,
+    texts: &mut Query<&mut Text>,
+    node: &mut AccessibilityNode,
+    label: &str,
+) {
+    for child in children.into_iter().flatten() {
+        if let Ok(mut text) = texts.get_mut(*child) {
+            text.sections[0].value = label.to_string();
         }
     }
+    node.set_name(label);
 }
 
-fn setup_score(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    state: Res<GameState>,
-    mut old_high_score: ResMut<OldHighScore>,
-    mut high_score: ResMut<HighScore>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+fn reset_settings(
+    mut settings: ResMut<Settings>,
+    mut buttons: Query<(&ButtonAction, Option<&Children>, &mut AccessibilityNode)>,
+    mut texts: Query<&mut Text>,
+    mut announce: EventWriter<Announce>,
 ) {
-    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
-    let text_style = TextStyle {
-        font: font.clone(),
-        font_size: 80.0,
-        color: Color::BLACK,
-    };
-    let text_justification = JustifyText::Center;
+    *settings = default_settings();
+    settings.save();
 
-    commands.spawn((
-        Text2dBundle {
-            text: Text::from_section(format!("Score: {}", state.max_idx), text_style.clone())
-                .with_justify(text_justification),
-            transform: Transform::from_xyz(0.0, 0.0, 0.0),
-            ..default()
-        },
-        SceneObject(()),
-    ));
+    for (action, children, mut node) in &mut buttons {
+        if let ButtonAction::ToggleSetting(setting) = action {
+            set_button_label(children, &mut texts, &mut node, &setting.label(&settings));
+        }
+    }
+    announce.send(Announce("Settings reset".to_string()));
+}
 
-    if state.max_idx > high_score.0 {
-        old_high_score.0 = high_score.0;
-        high_score.0 = state.max_idx;
-        save_score(high_score.0);
-        commands.spawn((
-            Text2dBundle {
-                text: Text::from_section(&format!("NEW HIGH SCORE!"), text_style.clone())
-                    .with_justify(text_justification),
-                transform: Transform::from_xyz(0.0, 80.0, 0.0),
-                ..default()
-            },
-            SceneObject(()),
-        ));
-        commands.spawn((
-            Text2dBundle {
-                text: Text::from_section(&format!("Old High Score: {}", old_high_score.0), text_style.clone())
-                    .with_justify(text_justification),
-                transform: Transform::from_xyz(0.0, -80.0, 0.0),
-                ..default()
-            },
-            SceneObject(()),
-        ));
+/// Tab and the arrow keys move focus between the buttons in the scene, top
+/// to bottom then left to right
+fn menu_focus_system(
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Query<(Entity, &GlobalTransform, &AccessibilityNode, Has<Node>)>,
+    mut focus: ResMut<Focus>,
+    mut announce: EventWriter<Announce>,
+) {
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let step: isize = if keys.just_pressed(KeyCode::Tab) {
+        if shift { -1 } else { 1 }
+    } else if keys.any_just_pressed([KeyCode::ArrowDown, KeyCode::ArrowRight]) {
+        1
+    } else if keys.any_just_pressed([KeyCode::ArrowUp, KeyCode::ArrowLeft]) {
+        -1
     } else {
-        commands.spawn((
-            Text2dBundle {
-                text: Text::from_section(&format!("High Score: {}", high_score.0), text_style.clone())
-                    .with_justify(text_justification),
-                transform: Transform::from_xyz(0.0, -80.0, 0.0),
-                ..default()
-            },
-            SceneObject(()),
-        ));
+        return;
+    };
+
+    // UI positions count down from the top of the window, world space ones
+    // count up from the middle
+    let mut focusable: Vec<(Entity, Vec2, &AccessibilityNode)> = buttons
+        .iter()
+        .filter(|(_entity, _transform, node, _ui)| node.role() == Role::Button && !node.is_disabled())
+        .map(|(entity, transform, node, ui)| {
+            let position = transform.translation().xy();
+            (entity, if ui { position } else { Vec2::new(position.x, -position.y) }, node)
+        })
+        .collect();
+    if focusable.is_empty() {
+        return;
     }
+    focusable.sort_by(|(_, a, _), (_, b, _)| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)));
 
-    add_scene_change_button(
-        &asset_server,
-        &mut commands,
-        &mut materials,
-        &mut meshes,
-        "Click to return",
-        Color::hsl(235.0, 0.95, 0.7),
-        Color::hsl(235.0, 0.95, 0.3),
-        500.0,
-        60.0,
-        Transform::from_xyz(0.0, -240.0, 0.0),
-        Scene::MainMenu,
-    );
+    let next = match focusable.iter().position(|(entity, _, _)| Some(*entity) == focus.0) {
+        Some(current) => (current as isize + step).rem_euclid(focusable.len() as isize) as usize,
+        None if step > 0 => 0,
+        None => focusable.len() - 1,
+    };
+
+    let (entity, _, node) = focusable[next];
+    focus.0 = Some(entity);
+    if let Some(name) = node.name() {
+        announce.send(Announce(name.to_string()));
+    }
 }
 
-fn add_scene_change_button(
-    asset_server: &Res<AssetServer>,
-    commands: &mut Commands,
-    materials: &mut ResMut<Assets<ColorMaterial>>,
-    meshes: &mut ResMut<Assets<Mesh>>,
-    text: &str,
-    color: Color,
-    hover_color: Color,
-    // TODO: need to use UI system to measure text size...
-    width: f32,
-    height: f32,
-    mut transform: Transform,
-    scene: Scene,
+/// The same text twice in a row, like playback lighting the same pad twice,
+/// leaves the live region unchanged and screen readers stay quiet. A repeat
+/// clears the region first and is announced on the next frame instead.
+fn announce_system(
+    settings: Res<Settings>,
+    mut events: EventReader<Announce>,
+    mut announcer: Query<&mut AccessibilityNode, With<Announcer>>,
+    mut last: Local<String>,
+    mut repeat: Local<Option<String>>,
 ) {
-    // TODO: somehow it seems like this should be one entity...
-    // But the text needs a different transform (Z) than the rectangle
-    // Somehow you can create things with children, but that's a problem for
-    // another time
+    if !settings.accessibility {
+        events.clear();
+        return;
+    }
 
-    // Button rectangle
-    commands.spawn((
-        MaterialMesh2dBundle {
-            mesh: Mesh2dHandle(meshes.add(Rectangle::new(width, height))),
-            material: materials.add(color),
-            transform: transform,
-            ..default()
-        },
-        MouseHoverTracker::from_rect(width, height),
-        MouseOverMaterial(materials.add(hover_color)),
-        MouseOutMaterial(materials.add(color)),
-        SceneObject(()),
-    ));
+    // Everything from one frame is read out together so quick presses don't
+    // interrupt each other
+    let mut parts: Vec<String> = repeat.take().into_iter().collect();
+    parts.extend(events.read().map(|announcement| announcement.0.clone()));
+    let text = parts.join(". ");
+    if text.is_empty() {
+        return;
+    }
 
-    // Button text/action
-    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
-    let text_style = TextStyle {
-        font: font.clone(),
-        font_size: 60.0,
-        color: Color::BLACK,
-    };
-    let text_justification = JustifyText::Center;
-    let text_style = TextStyle {
-        font: font.clone(),
-        font_size: 60.0,
-        color: Color::BLACK,
+    let mut set_text = |text: &str| {
+        #[cfg(target_arch = "wasm32")]
+        wasm::announce(text);
+
+        for mut node in &mut announcer {
+            node.set_name(text);
+        }
     };
-    let text_justification = JustifyText::Center;
 
-    transform.translation += Vec3::new(0.0, 0.0, 1.0);
+    if text == *last {
+        set_text("");
+        last.clear();
+        *repeat = Some(text);
+        return;
+    }
 
-    commands.spawn((
-        Text2dBundle {
-            text: Text::from_section(text, text_style.clone())
-                .with_justify(text_justification),
-            transform: transform,
-            ..default()
-        },
-        MouseHoverTracker::from_rect(width, height),
-        SceneChangeButton {
-            width,
-            height,
-            scene,
-        },
-        SceneObject(()),
-    ));
+    println!("Announcing: {text}");
+    set_text(&text);
+    *last = text;
 }
 
-fn handle_scene_change(
-    next_scene: Res<NextScene>,
-    scene_setup_system: Res<SceneSetupSystem>,
-    scene_objects: Query<Entity, With<SceneObject>>,
+#[allow(clippy::too_many_arguments)]
+fn ghost_playback_system(
     mut commands: Commands,
-    mut current_scene: ResMut<CurrentScene>,
+    time: Res<Time>,
+    mode: Res<SelectedMode>,
+    best_run: Res<BestRun>,
+    state: Res<GameState>,
+    settings: Res<Settings>,
+    pads: Query<(&PatternIdx, &MouseHoverTracker, &GlobalTransform)>,
+    mut ghost: ResMut<GhostCursor>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    // Check if we're updating the scene
-    if next_scene.0 != current_scene.0 {
-        let scene = next_scene.0;
-        current_scene.0 = scene;
+    if mode.0 != GameMode::Ghost || !state.interactive {
+        return;
+    }
 
-        println!("Switching to {scene:?}");
+    // The ghost simply stops once it's past the round where it lost
+    let Some(presses) = best_run.0.as_ref().and_then(|run| run.rounds.get(state.max_idx as usize)) else {
+        return;
+    };
 
-        // Remove any scene-specific entities
-        println!("Removing scene objects");
-        for obj in &scene_objects {
-            commands.entity(obj).despawn();
+    let elapsed = (time.elapsed_seconds_f64() - state.round_start) as f32;
+    while let Some(press) = presses.get(ghost.0) {
+        if press.time > elapsed {
+            break;
         }
+        ghost.0 += 1;
 
-        // Run the setup system for the new scene
-        if let Some(system) = scene_setup_system.system_map.get(&scene) {
-            commands.run_system(*system);
-        } else {
-            println!("NOTE: Transitioning to scene {scene:?} which does not have a setup system");
+        for (idx, tracker, transform) in &pads {
+            if idx.0 != press.pad {
+                continue;
+            }
+            let center = transform.transform_point(tracker.shape.center().extend(0.0));
+            commands.spawn((
+                MaterialMesh2dBundle {
+                    mesh: Mesh2dHandle(meshes.add(Circle::new(30.0))),
+                    material: materials.add(Color::rgba(0.0, 0.0, 0.0, ghost_flash_alpha(settings.reduced_flash))),
+                    transform: Transform::from_xyz(center.x, center.y, 0.5),
+                    ..default()
+                },
+                GhostFlash(Timer::from_seconds(0.4, TimerMode::Once)),
+                SceneObject(()),
+            ));
         }
     }
 }
 
-fn update_mouse_position(
-    window: Query<&Window, With<PrimaryWindow>>,
-    camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
-    mut mouse: ResMut<ShmMousePosition>,
-) {
-    let (camera, camera_transform) = camera.single();
-    let window = window.single();
+const GHOST_FLASH_ALPHA: f32 = 0.25;
 
-    if let Some(world_position) = window.cursor_position()
-        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
-        .map(|ray| ray.origin.truncate())
-    {
-        mouse.pos = Some(world_position);
-    } else {
-        mouse.pos = None;
-    }
-}
+/// A black marker at this alpha darkens what's under it by at most the
+/// reduced flash lightness change
+const REDUCED_FLASH_GHOST_FLASH_ALPHA: f32 = REDUCED_FLASH_MAX_LIGHTNESS_CHANGE;
 
-fn update_mouse_hover_disable(
-    mut tracked_objects: Query<&mut MouseHoverTracker, With<MouseHoverDisable>>,
-) {
-    for mut tracker in &mut tracked_objects {
-        tracker.set_hovered(false);
+fn ghost_flash_alpha(reduced_flash: bool) -> f32 {
+    if reduced_flash {
+        GHOST_FLASH_ALPHA.min(REDUCED_FLASH_GHOST_FLASH_ALPHA)
+    } else {
+        GHOST_FLASH_ALPHA
     }
 }
 
-fn update_mouse_hover_state(
-    mouse: ResMut<ShmMousePosition>,
-    mut tracked_objects: Query<(&mut MouseHoverTracker, &Transform), Without<MouseHoverDisable>>,
+fn ghost_flash_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut query: Query<(Entity, &mut GhostFlash, &Handle<ColorMaterial>)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    if let Some(mouse_pos) = mouse.pos {
-        for (mut tracker, transform) in &mut tracked_objects {
-            let local_mouse_pos = transform.compute_matrix().inverse().transform_point3(mouse_pos.extend(0.0)).xy();
-            let hovered = match tracker.shape {
-                HoverShape::Rectangle(r) => {
-                    -r.x <= local_mouse_pos.x && local_mouse_pos.x <= r.x
-                        && -r.y <= local_mouse_pos.y && local_mouse_pos.y <= r.y
-                }
-                HoverShape::Triangle(a, b, c) => {
-                    check_collision_point_tri(local_mouse_pos, a, b, c)
-                }
-            };
-            tracker.set_hovered(hovered);
+    for (entity, mut flash, handle) in &mut query {
+        flash.0.tick(time.delta());
+        if let Some(material) = materials.get_mut(handle) {
+            material.color.set_a(ghost_flash_alpha(settings.reduced_flash) * flash.0.fraction_remaining());
         }
-    } else {
-        for (mut tracker, _transform) in &mut tracked_objects {
-            tracker.set_hovered(false);
+        if flash.0.finished() {
+            commands.entity(entity).despawn();
         }
     }
 }
 
-fn update_mouse_hover_material(
-    mut query: Query<(&MouseHoverTracker, &MouseOverMaterial, &mut Handle<ColorMaterial>)>,
+/// Leaving a paused game unpauses, so the scene has to be checked too or
+/// playback would carry on over the next scene
+fn game_running(current_scene: Res<CurrentScene>, paused: Res<Paused>) -> bool {
+    current_scene.0 == Scene::Game && !paused.0
+}
+
+/// Escape pauses and resumes a game, where the pause menu doubles as the
+/// confirmation before quitting. Anywhere else it quits on desktop.
+#[allow(clippy::too_many_arguments)]
+fn escape_system(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    current_scene: Res<CurrentScene>,
+    mut paused: ResMut<Paused>,
+    mut time: ResMut<Time<Virtual>>,
+    button_systems: Res<ButtonSystems>,
+    asset_server: Res<AssetServer>,
+    mut announce: EventWriter<Announce>,
+    mut quit: EventWriter<QuitRequested>,
 ) {
-    for (tracker, material_info, mut material) in &mut query {
-        if tracker.is_just_hovered {
-            *material = material_info.0.clone();
+    if !keys.just_pressed(KeyCode::Escape) {
+        return;
+    }
+
+    if current_scene.0 != Scene::Game {
+        // Browsers don't let a page close its own tab
+        if cfg!(not(target_arch = "wasm32")) {
+            quit.send(QuitRequested);
         }
+        return;
     }
+
+    if paused.0 {
+        commands.run_system(button_systems.resume_game);
+        return;
+    }
+
+    println!("Pausing game");
+    paused.0 = true;
+    time.pause();
+    spawn_pause_menu(&mut commands, &asset_server.load("fonts/FiraSans-Bold.ttf"), &button_systems);
+    announce.send(Announce("Paused".to_string()));
 }
 
-fn update_mouse_unhover_material(
-    mut query: Query<(&MouseHoverTracker, &MouseOutMaterial, &mut Handle<ColorMaterial>)>,
+fn spawn_pause_menu(commands: &mut Commands, font: &Handle<Font>, button_systems: &ButtonSystems) {
+    commands.spawn((
+        SpatialBundle::from_transform(Transform::from_xyz(0.0, 0.0, 50.0)),
+        MouseHoverTracker::from_rect(99999., 99999.).with_priority(i32::MAX),
+        PauseMenu,
+        SceneObject(()),
+    ));
+
+    let mut root = menu_root(JustifyContent::Center);
+    root.background_color = Color::rgba(0.96, 0.96, 0.96, 0.85).into();
+    root.z_index = ZIndex::Global(10);
+    commands.spawn((root, PauseMenu, SceneObject(()))).with_children(|menu| {
+        menu.spawn(menu_title("Paused", font));
+
+        add_button(
+            menu,
+            font,
+            "Resume",
+            Color::rgb_u8(0, 228, 48),
+            Color::rgb_u8(0, 158, 47),
+            ButtonAction::RunSystem(button_systems.resume_game),
+        );
+
+        add_button(
+            menu,
+            font,
+            "Main Menu",
+            Color::rgb_u8(0, 121, 241),
+            Color::rgb_u8(0, 82, 172),
+            ButtonAction::ChangeScene(Scene::MainMenu),
+        );
+
+        #[cfg(not(target_arch = "wasm32"))]
+        add_button(
+            menu,
+            font,
+            "Quit",
+            Color::rgb_u8(200, 200, 200),
+            Color::rgb_u8(130, 130, 130),
+            ButtonAction::Quit,
+        );
+    });
+}
+
+fn resume_game(
+    mut commands: Commands,
+    pause_menu: Query<Entity, With<PauseMenu>>,
+    mut paused: ResMut<Paused>,
+    mut time: ResMut<Time<Virtual>>,
+    mut announce: EventWriter<Announce>,
 ) {
-    for (tracker, material_info, mut material) in &mut query {
-        if tracker.is_just_unhovered {
-            *material = material_info.0.clone();
-        }
+    println!("Resuming game");
+    for entity in &pause_menu {
+        commands.entity(entity).despawn_recursive();
     }
+    paused.0 = false;
+    time.unpause();
+    announce.send(Announce("Resumed".to_string()));
 }
 
-fn scene_change_button(
-    query: Query<(&SceneChangeButton, &MouseHoverTracker)>,
-    mouse: Res<ButtonInput<MouseButton>>,
-    mouse_pos: Res<ShmMousePosition>,
+fn continue_game(
+    mut resumable: ResMut<ResumableGame>,
+    mut mode: ResMut<SelectedMode>,
     mut next_scene: ResMut<NextScene>,
 ) {
-    // If we just click the mouse button in frame, find if any scene change
-    // buttons were hovered.
-    if mouse.just_released(MouseButton::Left) {
-        if let Some(mouse_pos) = mouse_pos.pos {
-            for (button, tracker) in &query {
-                if tracker.is_hovered {
-                    println!("Requesting switch to {:?}", button.scene);
-                    next_scene.0 = button.scene;
-                    break;
-                }
-            }
-        }
-    }
+    let Some(saved) = &resumable.saved else {
+        return;
+    };
+    mode.0 = saved.mode;
+    resumable.resume_next = true;
+    next_scene.0 = Scene::Game;
 }
 
-pub fn close_on_esc(
-    mut commands: Commands,
-    focused_windows: Query<(Entity, &Window)>,
-    input: Res<ButtonInput<KeyCode>>,
+/// Settings are saved as they change and scores as games end, but they're
+/// written again before exiting in case anything was missed. A game in
+/// progress is saved so it can be continued, whether the app quits itself or
+/// the window is closed.
+#[allow(clippy::too_many_arguments)]
+fn quit_system(
+    mut requests: EventReader<QuitRequested>,
+    mut close_requests: EventReader<WindowCloseRequested>,
+    settings: Res<Settings>,
+    high_scores: Res<HighScores>,
+    current_scene: Res<CurrentScene>,
+    mode: Res<SelectedMode>,
+    state: Res<GameState>,
+    game_over: Res<PendingGameOver>,
+    mut resumable: ResMut<ResumableGame>,
+    mut app_exit: EventWriter<AppExit>,
 ) {
-    for (window, focus) in focused_windows.iter() {
-        if !focus.focused {
-            continue;
-        }
+    let closing = close_requests.read().count() > 0;
+    if requests.read().count() == 0 && !closing {
+        return;
+    }
 
-        if input.just_pressed(KeyCode::Escape) {
-            commands.entity(window).despawn();
-        }
+    if current_scene.0 == Scene::Game && game_over.0.is_none() {
+        resumable.save(mode.0, &state);
     }
+    if closing {
+        // Bevy closes the window itself, which exits the app
+        return;
+    }
+
+    println!("Saving before quitting");
+    settings.save();
+    for (&mode, &score) in high_scores.0.iter().filter(|(_, &score)| score > 0) {
+        save_high_score(mode, score);
+    }
+    app_exit.send(AppExit);
 }
 
 pub struct ShmPlugin;
@@ -944,24 +4640,75 @@ impl Plugin for ShmPlugin {
             .insert_resource(ClearColor(Color::rgb_u8(245, 245, 245)))
             .insert_resource(NextScene(Scene::ClickToStart))
             .insert_resource(GameState::new())
-            .insert_resource(ShmMousePosition { pos: None })
-            .insert_resource(PatternAnimationTimer(Timer::from_seconds(1.0, TimerMode::Repeating)))
-            .insert_resource(HighScore(load_score()))
+            .insert_resource(PointerPositions::default())
+            .insert_resource(PatternAnimationTimer(Timer::from_seconds(PATTERN_STEP_SECONDS, TimerMode::Repeating)))
+            .insert_resource(SprintRecord(load_sprint_record()))
+            .insert_resource(load_settings())
+            .add_event::<Announce>()
+            .add_event::<PadPressed>()
+            .add_event::<TweenCompleted>()
+            .add_event::<ButtonActivated>()
+            .add_event::<QuitRequested>()
+            .insert_resource(HoverGrid::default())
+            .add_event::<HoverEnter>()
+            .add_event::<HoverExit>()
+            .add_event::<Pressed>()
+            .add_event::<Clicked>()
+            .insert_resource(PendingGameOver::default())
+            .insert_resource(Paused::default())
+            .insert_resource(load_high_scores())
+            .insert_resource(load_resumable_game())
+            .insert_resource(load_stats())
             .insert_resource(OldHighScore(0))
+            .insert_resource(SelectedMode(GameMode::Classic))
+            .insert_resource(BestRun(load_best_run()))
+            .insert_resource(GhostCursor::default())
             .add_systems(Startup, (setup, load_assets).chain())
             .add_systems(
                 Update,
                 (
-                    update_mouse_position,
-                    update_mouse_hover_state,
-                    update_mouse_hover_disable,
-                    update_mouse_hover_material,
-                    update_mouse_unhover_material,
-                    pattern_playback_system,
-                    user_game_system,
-                    scene_change_button,
-                    handle_scene_change,
-                    close_on_esc,
+                    (
+                        update_pointer_positions,
+                        update_hover_cache,
+                        rebuild_hover_grid,
+                        update_mouse_hover_state,
+                        update_mouse_hover_disable,
+                        update_mouse_press_state,
+                        update_mouse_hover_material,
+                        update_mouse_unhover_material,
+                        update_mouse_pressed_material,
+                    )
+                        .chain(),
+                    (
+                        pattern_playback_system,
+                        user_game_system,
+                        pad_feedback_system,
+                        pad_press_animation_system,
+                        pad_shake_system,
+                        particle_system,
+                        game_over_system,
+                        ghost_playback_system,
+                        ghost_flash_system,
+                        round_hud_system,
+                    )
+                        .chain()
+                        .run_if(game_running),
+                    (
+                        tween_system,
+                        hide_faded_text_system,
+                    )
+                        .chain(),
+                    (
+                        menu_focus_system,
+                        menu_button_system,
+                        world_button_system,
+                        button_action_system,
+                        handle_scene_change,
+                        announce_system,
+                        escape_system,
+                        quit_system,
+                    )
+                        .chain(),
                 )
                     .chain(),
             );
@@ -971,3 +4718,302 @@ impl Plugin for ShmPlugin {
 fn main() {
     App::new().add_plugins((DefaultPlugins, ShmPlugin)).run();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(pad: u8, time: f32) -> RecordedPress {
+        RecordedPress { pad, time }
+    }
+
+    #[test]
+    fn input_time_skips_the_failed_round() {
+        let state = GameState {
+            pattern: vec![0, 1, 2],
+            max_idx: 2,
+            rounds: vec![vec![press(0, 1.0)], vec![press(0, 0.5), press(1, 1.5)], vec![press(3, 0.4)]],
+            ..default()
+        };
+        assert_eq!(state.input_time(), 2.5);
+    }
+
+    #[test]
+    fn input_time_counts_every_round_of_a_completed_sprint() {
+        let state = GameState {
+            pattern: vec![0, 1],
+            max_idx: 2,
+            length_limit: Some(2),
+            rounds: vec![vec![press(0, 1.0)], vec![press(0, 0.5), press(1, 1.5)]],
+            ..default()
+        };
+        assert_eq!(state.input_time(), 2.5);
+    }
+
+    #[test]
+    fn recording_round_times_skip_the_failed_round() {
+        let recording = RunRecording {
+            pattern: vec![0, 1],
+            rounds: vec![vec![press(0, 1.25)], vec![press(2, 0.5)]],
+        };
+        assert_eq!(recording.score(), 1);
+        assert_eq!(recording.round_time(0), Some(1.25));
+        assert_eq!(recording.round_time(1), None);
+    }
+
+    #[test]
+    fn recording_round_trips_through_save_string() {
+        let recording = RunRecording {
+            pattern: vec![0, 1, 2, 3],
+            rounds: vec![vec![press(0, 1.25)], vec![press(0, 0.5), press(3, 1.5)]],
+        };
+        let loaded = RunRecording::from_save_string(&recording.to_save_string()).unwrap();
+        assert_eq!(loaded.pattern, recording.pattern);
+        assert_eq!(loaded.rounds.len(), 2);
+        assert_eq!(loaded.rounds[1][1].pad, 3);
+        assert_eq!(loaded.rounds[1][1].time, 1.5);
+    }
+
+    #[test]
+    fn recording_rejects_empty_and_corrupt_save_strings() {
+        assert!(RunRecording::from_save_string("").is_none());
+        assert!(RunRecording::from_save_string("0123").is_none());
+        assert!(RunRecording::from_save_string("0193\n0:1.0").is_none());
+        assert!(RunRecording::from_save_string("0123\n0-1.0").is_none());
+    }
+
+    #[test]
+    fn saved_game_round_trips_through_save_string() {
+        let state = GameState {
+            pattern: vec![3, 2, 1, 0, 1],
+            max_idx: 2,
+            seed: 1234567890123,
+            ..default()
+        };
+        let saved = SavedGame::new(GameMode::Reverse, &state).unwrap();
+        assert_eq!(saved.pattern, vec![3, 2, 1]);
+
+        let loaded = SavedGame::from_save_string(&saved.to_save_string()).unwrap();
+        assert_eq!(loaded.mode, GameMode::Reverse);
+        assert_eq!(loaded.seed, 1234567890123);
+        assert_eq!(loaded.max_idx, 2);
+        assert_eq!(loaded.pattern, vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn saved_game_only_for_resumable_modes() {
+        let state = GameState { pattern: vec![0], ..default() };
+        assert!(SavedGame::new(GameMode::HotSeat(2), &state).is_none());
+        assert!(SavedGame::new(GameMode::Sprint, &state).is_none());
+        assert!(SavedGame::new(GameMode::Classic, &state).is_some());
+    }
+
+    #[test]
+    fn saved_game_rejects_empty_and_corrupt_save_strings() {
+        assert!(SavedGame::from_save_string("").is_none());
+        assert!(SavedGame::from_save_string("mode=sprint\nseed=1\nmax_idx=0\npattern=0").is_none());
+        assert!(SavedGame::from_save_string("mode=classic\nseed=x\nmax_idx=0\npattern=0").is_none());
+        assert!(SavedGame::from_save_string("mode=classic\nseed=1\nmax_idx=0\npattern=05").is_none());
+        assert!(SavedGame::from_save_string("mode=classic\nseed=1\npattern=0").is_none());
+    }
+
+    #[test]
+    fn saved_game_rejects_a_round_that_does_not_match_the_pattern() {
+        assert!(SavedGame::from_save_string("mode=classic\nseed=1\nmax_idx=2\npattern=01").is_none());
+        assert!(SavedGame::from_save_string("mode=classic\nseed=1\nmax_idx=0\npattern=01").is_none());
+        assert!(SavedGame::from_save_string("mode=add_one\nseed=1\nmax_idx=2\npattern=012").is_none());
+        assert!(SavedGame::from_save_string("mode=add_one\nseed=1\nmax_idx=2\npattern=01").is_some());
+
+        let full = "0".repeat(MAX_PATTERN_LEN as usize);
+        let save = format!("mode=add_one\nseed=1\nmax_idx={MAX_PATTERN_LEN}\npattern={full}");
+        assert!(SavedGame::from_save_string(&save).is_none());
+    }
+
+    fn stats_with_scores(scores: &[u8]) -> LifetimeStats {
+        let mut stats = LifetimeStats::default();
+        for &score in scores {
+            *stats.score_counts.entry(score).or_default() += 1;
+        }
+        stats
+    }
+
+    #[test]
+    fn stats_median_of_an_even_number_of_games() {
+        let stats = stats_with_scores(&[2, 9, 4, 4, 7, 1]);
+        assert_eq!(stats.games_played(), 6);
+        assert_eq!(stats.median_score(), Some(4.0));
+        assert_eq!(stats_with_scores(&[1, 2, 5, 8]).median_score(), Some(3.5));
+        assert_eq!(stats_with_scores(&[3, 8, 5]).median_score(), Some(5.0));
+        assert_eq!(stats_with_scores(&[]).median_score(), None);
+        assert_eq!(stats_with_scores(&[]).average_score(), None);
+    }
+
+    #[test]
+    fn stats_histogram_fits_the_highest_score() {
+        let (bucket_size, buckets) = stats_with_scores(&[0, 255, 255, 130]).score_buckets();
+        assert!(buckets.len() as u32 <= HISTOGRAM_BUCKETS);
+        assert_eq!(buckets.iter().sum::<u32>(), 4);
+        assert_eq!(buckets[0], 1);
+        assert_eq!(*buckets.last().unwrap(), 2);
+        assert!(255 < buckets.len() as u32 * bucket_size);
+
+        let (bucket_size, buckets) = stats_with_scores(&[0, 3]).score_buckets();
+        assert_eq!(bucket_size, 1);
+        assert_eq!(buckets, vec![1, 0, 0, 1]);
+    }
+
+    #[test]
+    fn stats_record_presses_and_misses() {
+        let state = GameState {
+            max_idx: 1,
+            rounds: vec![vec![press(0, 1.0)], vec![press(0, 0.5), press(2, 0.9)]],
+            ..default()
+        };
+        let mut stats = LifetimeStats::default();
+        stats.record_game(&state, Some(1), 90);
+        assert_eq!(stats.pads_pressed, 3);
+        assert_eq!(stats.correct_presses, 2);
+        assert_eq!(stats.most_missed_pad(), Some(1));
+        assert_eq!(stats.longest_session_seconds, 90);
+    }
+
+    #[test]
+    fn stats_round_trip_through_save_string() {
+        let mut stats = stats_with_scores(&[0, 5, 5, 255]);
+        stats.pads_pressed = 40;
+        stats.correct_presses = 37;
+        stats.misses = [1, 0, 2, 0];
+        stats.longest_session_seconds = 3700;
+
+        let mut loaded = LifetimeStats::default();
+        loaded.load_save_string(&stats.to_save_string());
+        assert_eq!(loaded.score_counts, stats.score_counts);
+        assert_eq!(loaded.pads_pressed, 40);
+        assert_eq!(loaded.correct_presses, 37);
+        assert_eq!(loaded.misses, [1, 0, 2, 0]);
+        assert_eq!(loaded.longest_session_seconds, 3700);
+    }
+
+    #[test]
+    fn stats_ignore_empty_and_corrupt_save_strings() {
+        let mut stats = LifetimeStats::default();
+        stats.load_save_string("");
+        assert_eq!(stats.games_played(), 0);
+
+        stats.load_save_string("scores=3:2,x:1,4\npads_pressed=lots\nmisses=1,y\nnonsense");
+        assert_eq!(stats.score_counts, BTreeMap::from([(3, 2)]));
+        assert_eq!(stats.pads_pressed, 0);
+        assert_eq!(stats.misses, [1, 0, 0, 0]);
+    }
+
+    fn assert_near(a: Vec2, b: Vec2) {
+        assert!(a.distance(b) < 1e-4, "{a} is not near {b}");
+    }
+
+    #[test]
+    fn reduced_flash_caps_the_error_tint() {
+        for palette in Palette::iter() {
+            for pad in 0..4 {
+                let (base, _) = palette.pad_colors(pad);
+                let capped = cap_lightness_change(base, PAD_ERROR_COLOR, REDUCED_FLASH_MAX_LIGHTNESS_CHANGE);
+                assert!((capped.l() - base.l()).abs() <= REDUCED_FLASH_MAX_LIGHTNESS_CHANGE + 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn circle_hit_test() {
+        assert!(check_collision_point_circle(Vec2::new(3.0, 4.0), 5.0));
+        assert!(!check_collision_point_circle(Vec2::new(3.0, 4.1), 5.0));
+    }
+
+    #[test]
+    fn polygon_hit_test_handles_concave_polygons() {
+        let notched = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(10.0, 10.0),
+            Vec2::new(5.0, 2.0),
+            Vec2::new(0.0, 10.0),
+        ];
+        assert!(check_collision_point_polygon(Vec2::new(5.0, 1.0), &notched));
+        assert!(check_collision_point_polygon(Vec2::new(9.0, 8.0), &notched));
+        // In the notch between the two points
+        assert!(!check_collision_point_polygon(Vec2::new(5.0, 6.0), &notched));
+        assert!(!check_collision_point_polygon(Vec2::new(-1.0, 1.0), &notched));
+        assert!(!check_collision_point_polygon(Vec2::ZERO, &[]));
+    }
+
+    #[test]
+    fn rounded_rect_hit_test_cuts_the_corners() {
+        let half_size = Vec2::new(10.0, 5.0);
+        assert!(check_collision_point_rounded_rect(Vec2::new(9.9, 0.0), half_size, 2.0));
+        assert!(check_collision_point_rounded_rect(Vec2::new(-9.0, -4.0), half_size, 2.0));
+        assert!(!check_collision_point_rounded_rect(Vec2::new(9.9, 4.9), half_size, 2.0));
+        assert!(!check_collision_point_rounded_rect(Vec2::new(10.1, 0.0), half_size, 2.0));
+    }
+
+    #[test]
+    fn annular_sector_hit_test() {
+        use std::f32::consts::{FRAC_PI_2, PI};
+
+        // The top left quarter of a ring
+        let hit = |p| check_collision_point_annular_sector(p, 5.0, 10.0, FRAC_PI_2, PI);
+        assert!(hit(Vec2::new(-5.0, 5.0)));
+        assert!(!hit(Vec2::new(-2.0, 2.0)));
+        assert!(!hit(Vec2::new(-8.0, 8.0)));
+        assert!(!hit(Vec2::new(5.0, 5.0)));
+
+        // Sectors that cross the negative x axis wrap around
+        let hit = |p| check_collision_point_annular_sector(p, 5.0, 10.0, 3.0 * FRAC_PI_2, 5.0 * FRAC_PI_2);
+        assert!(hit(Vec2::new(5.0, -5.0)));
+        assert!(hit(Vec2::new(5.0, 5.0)));
+        assert!(!hit(Vec2::new(-5.0, 5.0)));
+    }
+
+    #[test]
+    fn polygon_center_of_a_concave_polygon() {
+        let notched = HoverShape::Polygon(vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(10.0, 10.0),
+            Vec2::new(5.0, 2.0),
+            Vec2::new(0.0, 10.0),
+        ]);
+        assert_near(notched.center(), Vec2::new(5.0, 31.0 / 9.0));
+
+        // Winding doesn't matter
+        let square = HoverShape::Polygon(vec![
+            Vec2::new(2.0, 2.0),
+            Vec2::new(2.0, 4.0),
+            Vec2::new(4.0, 4.0),
+            Vec2::new(4.0, 2.0),
+        ]);
+        assert_near(square.center(), Vec2::new(3.0, 3.0));
+    }
+
+    #[test]
+    fn triangles_center_ignores_winding() {
+        let shape = HoverShape::Triangles(vec![
+            [Vec2::new(0.0, 0.0), Vec2::new(2.0, 0.0), Vec2::new(2.0, 2.0)],
+            [Vec2::new(0.0, 0.0), Vec2::new(0.0, 2.0), Vec2::new(2.0, 2.0)],
+        ]);
+        assert_near(shape.center(), Vec2::new(1.0, 1.0));
+    }
+
+    #[test]
+    fn shape_from_mesh() {
+        let quad = Mesh::from(Rectangle::new(4.0, 2.0));
+        let shape = HoverShape::from_mesh(&quad).unwrap();
+        assert!(matches!(shape, HoverShape::Triangles(ref triangles) if triangles.len() == 2));
+        assert!(shape.contains(Vec2::new(1.9, 0.9)));
+        assert!(!shape.contains(Vec2::new(2.1, 0.0)));
+        assert_near(shape.center(), Vec2::ZERO);
+
+        let triangle = Mesh::from(Triangle2d::new(Vec2::new(0.0, 3.0), Vec2::new(-3.0, 0.0), Vec2::new(3.0, 0.0)));
+        assert!(matches!(HoverShape::from_mesh(&triangle), Some(HoverShape::Triangle(..))));
+
+        let lines = Mesh::new(PrimitiveTopology::LineList, RenderAssetUsages::default());
+        assert!(HoverShape::from_mesh(&lines).is_none());
+    }
+}